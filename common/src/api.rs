@@ -0,0 +1,288 @@
+//! The HTTP API of the server: the path, method and typed request and
+//! response of every endpoint. Clients build requests from these values
+//! instead of formatting URLs by hand.
+
+use serde::de::DeserializeOwned;
+
+use crate::path;
+use crate::{
+    CaptureRequest, CaptureResponse, CreateScratchResponse, DeleteFileResponse, ExportRequest,
+    ExportResponse, GetFileContentResponse, GetFilesResponse, GetVaultsResponse,
+    OpenExternalResponse, RenameFileRequest, RenameFileResponse, SaveFileContentRequest,
+    SaveFileContentResponse, UploadAttachmentResponse, DEFAULT_VAULT,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Method {
+    Get,
+    Post,
+    Delete,
+}
+
+impl Method {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Delete => "DELETE",
+        }
+    }
+}
+
+/// Body of a request, with its content type.
+pub struct Body {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Body {
+    fn json<T: serde::Serialize>(value: &T) -> Self {
+        Body {
+            content_type: "application/json".to_string(),
+            data: serde_json::to_vec(value).expect("serialize request body"),
+        }
+    }
+}
+
+/// Start of the paths that select a vault, e.g. `/api/vault/work/files` for
+/// `/api/files` on the notes of the vault `work`.
+pub const VAULT_PREFIX: &str = "/api/vault/";
+
+/// Server-Sent Events stream of the changes to notes in all vaults.
+pub const EVENTS_PATH: &str = "/api/events";
+
+/// Start of the paths attachments are served under, followed by the id of
+/// the attachment.
+pub const ATTACHMENT_PREFIX: &str = "/api/attachments/";
+
+/// A call to the API.
+pub trait Endpoint: Sized {
+    type Response: DeserializeOwned;
+
+    const METHOD: Method;
+
+    /// Route the server matches the path against. `{name}` stands for one
+    /// path segment and `{*name}` for the rest of the path.
+    const PATTERN: &'static str;
+
+    /// Path and query of the request, relative to the server.
+    fn path(&self) -> String;
+
+    /// Path and query of the request on the notes in `vault`. Endpoints that
+    /// don't depend on the vault ignore it.
+    fn path_in(&self, vault: &str) -> String {
+        vault_path(vault, &self.path())
+    }
+
+    /// Consumes the endpoint, so call [`Endpoint::path`] first.
+    fn body(self) -> Option<Body> {
+        None
+    }
+}
+
+/// `path` below `/api` moved into the vault segment of `vault`, e.g.
+/// `/api/vault/work/files` for `/api/files`. Paths in the default vault are
+/// kept as they are.
+pub fn vault_path(vault: &str, path: &str) -> String {
+    match path.strip_prefix("/api/") {
+        Some(rest) if vault != DEFAULT_VAULT => {
+            format!("{}{}/{}", VAULT_PREFIX, path::encode_component(vault), rest)
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Notes in the notes directory and the inbox whose names contain `key`.
+pub struct ListFiles {
+    pub key: String,
+}
+
+impl Endpoint for ListFiles {
+    type Response = GetFilesResponse;
+    const METHOD: Method = Method::Get;
+    const PATTERN: &'static str = "/api/files";
+
+    fn path(&self) -> String {
+        if self.key.is_empty() {
+            "/api/files".to_string()
+        } else {
+            format!("/api/files?key={}", path::encode_component(&self.key))
+        }
+    }
+}
+
+/// Content of a note. A note that doesn't exist reads as empty, without a
+/// modification time, if `create_missing` is set; it is created when it is
+/// first saved. The request fails with `404 Not Found` otherwise.
+pub struct GetFile {
+    pub name: String,
+    pub create_missing: bool,
+}
+
+impl Endpoint for GetFile {
+    type Response = GetFileContentResponse;
+    const METHOD: Method = Method::Get;
+    const PATTERN: &'static str = "/api/file/{*name}";
+
+    fn path(&self) -> String {
+        let path = format!("/api/file/{}", path::encode(&self.name));
+        if self.create_missing {
+            path
+        } else {
+            format!("{}?create=false", path)
+        }
+    }
+}
+
+pub struct SaveFile {
+    pub name: String,
+    pub request: SaveFileContentRequest,
+}
+
+impl Endpoint for SaveFile {
+    type Response = SaveFileContentResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/file/{*name}";
+
+    fn path(&self) -> String {
+        format!("/api/file/{}", path::encode(&self.name))
+    }
+
+    fn body(self) -> Option<Body> {
+        Some(Body::json(&self.request))
+    }
+}
+
+pub struct DeleteFile {
+    pub name: String,
+}
+
+impl Endpoint for DeleteFile {
+    type Response = DeleteFileResponse;
+    const METHOD: Method = Method::Delete;
+    const PATTERN: &'static str = "/api/file/{*name}";
+
+    fn path(&self) -> String {
+        format!("/api/file/{}", path::encode(&self.name))
+    }
+}
+
+pub struct RenameFile(pub RenameFileRequest);
+
+impl Endpoint for RenameFile {
+    type Response = RenameFileResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/rename";
+
+    fn path(&self) -> String {
+        "/api/rename".to_string()
+    }
+
+    fn body(self) -> Option<Body> {
+        Some(Body::json(&self.0))
+    }
+}
+
+/// Opens a note in the external editor configured on the server. Fails with
+/// `409 Conflict` if it is open there already.
+pub struct OpenExternal {
+    pub name: String,
+}
+
+impl Endpoint for OpenExternal {
+    type Response = OpenExternalResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/external/{*name}";
+
+    fn path(&self) -> String {
+        format!("/api/external/{}", path::encode(&self.name))
+    }
+}
+
+/// Names of the vaults the server keeps notes in.
+pub struct ListVaults;
+
+impl Endpoint for ListVaults {
+    type Response = GetVaultsResponse;
+    const METHOD: Method = Method::Get;
+    const PATTERN: &'static str = "/api/vaults";
+
+    fn path(&self) -> String {
+        "/api/vaults".to_string()
+    }
+
+    fn path_in(&self, _vault: &str) -> String {
+        self.path()
+    }
+}
+
+pub struct CreateScratch;
+
+impl Endpoint for CreateScratch {
+    type Response = CreateScratchResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/scratch";
+
+    fn path(&self) -> String {
+        "/api/scratch".to_string()
+    }
+}
+
+pub struct Capture(pub CaptureRequest);
+
+impl Endpoint for Capture {
+    type Response = CaptureResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/capture";
+
+    fn path(&self) -> String {
+        "/api/capture".to_string()
+    }
+
+    fn body(self) -> Option<Body> {
+        Some(Body::json(&self.0))
+    }
+}
+
+pub struct Export(pub ExportRequest);
+
+impl Endpoint for Export {
+    type Response = ExportResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/export";
+
+    fn path(&self) -> String {
+        "/api/export".to_string()
+    }
+
+    fn body(self) -> Option<Body> {
+        Some(Body::json(&self.0))
+    }
+}
+
+pub struct UploadAttachment {
+    /// Name of the uploaded file, used for the extension of the attachment.
+    pub name: Option<String>,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Endpoint for UploadAttachment {
+    type Response = UploadAttachmentResponse;
+    const METHOD: Method = Method::Post;
+    const PATTERN: &'static str = "/api/attachments";
+
+    fn path(&self) -> String {
+        match &self.name {
+            Some(name) => format!("/api/attachments?name={}", path::encode_component(name)),
+            None => "/api/attachments".to_string(),
+        }
+    }
+
+    fn body(self) -> Option<Body> {
+        Some(Body {
+            content_type: self.content_type,
+            data: self.data,
+        })
+    }
+}
@@ -1,5 +1,15 @@
 use serde::{Deserialize, Serialize};
 
+pub mod api;
+pub mod links;
+#[cfg(feature = "markdown")]
+pub mod markdown;
+pub mod path;
+
+/// Name of the vault kept in the server's `file_directory`. API paths
+/// without a vault segment refer to it.
+pub const DEFAULT_VAULT: &str = "default";
+
 #[derive(Deserialize, Serialize)]
 pub enum ItemType {
     File,
@@ -20,9 +30,144 @@ pub struct GetFilesResponse {
 #[derive(Deserialize, Serialize)]
 pub struct GetFileContentResponse {
     pub content: String,
+    /// Last modification time of the file in milliseconds since the epoch.
+    #[serde(default)]
+    pub modified: Option<u64>,
+    /// Whether the note is open in an external editor, so that it should
+    /// not be edited here.
+    #[serde(default)]
+    pub editing_externally: bool,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct SaveFileContentRequest {
     pub content: String,
+    /// Set for saves made while the user is still editing. Scratch notes are
+    /// neither renamed nor removed on autosave.
+    #[serde(default)]
+    pub autosave: bool,
+    /// Fail with `409 Conflict` instead of replacing an existing note.
+    #[serde(default)]
+    pub create_new: bool,
+    /// Modification time of the note the content is based on, as last read
+    /// or saved. The save fails with `409 Conflict` if the note was changed
+    /// since, e.g. by a capture. Not checked when `None`.
+    #[serde(default)]
+    pub expected_modified: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SaveFileContentResponse {
+    /// Name of the file after saving. A scratch note may be renamed from its
+    /// first heading, or removed when it was left empty (`None`).
+    pub name: Option<String>,
+    /// Modification time of the note after saving.
+    #[serde(default)]
+    pub modified: Option<u64>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct DeleteFileResponse {}
+
+#[derive(Deserialize, Serialize)]
+pub struct RenameFileRequest {
+    pub from: String,
+    /// New name of the note. Fails with `409 Conflict` if it exists.
+    pub to: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RenameFileResponse {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct OpenExternalResponse {}
+
+#[derive(Deserialize, Serialize)]
+pub struct GetVaultsResponse {
+    /// Names of the vaults, the default vault first.
+    pub vaults: Vec<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CreateScratchResponse {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CaptureRequest {
+    pub text: String,
+    /// Note to append to. Defaults to the server's capture note.
+    pub note: Option<String>,
+    /// Section of the note to append under.
+    pub heading: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CaptureResponse {
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ExportRequest {
+    /// Only export notes with this tag.
+    pub tag: Option<String>,
+    /// Only export notes below this folder.
+    pub folder: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct ExportResponse {
+    pub pages: usize,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct UploadAttachmentResponse {
+    pub id: String,
+    /// URL the attachment is served from, for use in Markdown links.
+    pub url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub enum FileEventKind {
+    Created,
+    Changed,
+    Removed,
+    /// The note was opened in an external editor.
+    EditingStarted,
+    /// The external editor of the note exited.
+    EditingEnded,
+}
+
+/// A change to a file in the notes directory, pushed to the frontend over
+/// `/api/events`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FileEvent {
+    pub kind: FileEventKind,
+    /// Name of the file relative to the notes directory.
+    pub name: String,
+    /// Vault the file is in. Stores leave it empty; the server fills it in
+    /// before streaming the event.
+    #[serde(default)]
+    pub vault: String,
+}
+
+/// A request from the app to the `webview` binary about its window.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum WindowRequest {
+    SetTitle(String),
+    /// The window was resized to this inner size.
+    Resized {
+        width: usize,
+        height: usize,
+    },
+}
+
+/// A request from the `webview` binary to the app in its window, e.g. for
+/// arguments forwarded by a later launch.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum AppCommand {
+    /// Open the note with this name in the editor.
+    Open(String),
 }
@@ -0,0 +1,195 @@
+//! Parsing of the note-to-note references used across scrapnote: wiki links
+//! (`[[target]]`, `[[target|label]]`) and tags (`#tag`).
+
+/// A wiki link found in a note. `start` and `end` are byte offsets of the
+/// whole `[[...]]` span in the note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WikiLink {
+    pub target: String,
+    pub label: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl WikiLink {
+    pub fn text(&self) -> &str {
+        self.label.as_deref().unwrap_or(&self.target)
+    }
+}
+
+/// A tag found in a note, without the leading `#`. `start` and `end` are byte
+/// offsets of the tag including the `#`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A Markdown ATX heading. `start` is the byte offset of its line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Heading {
+    pub level: usize,
+    pub title: String,
+    pub start: usize,
+}
+
+pub fn wiki_links(content: &str) -> Vec<WikiLink> {
+    let mut links = Vec::new();
+    for (offset, line) in prose_lines(content) {
+        let mut rest = 0;
+        while let Some(open) = line[rest..].find("[[") {
+            let open = rest + open;
+            let close = match line[open + 2..].find("]]") {
+                Some(c) => open + 2 + c,
+                None => break,
+            };
+            let inner = &line[open + 2..close];
+            let (target, label) = match inner.find('|') {
+                Some(bar) => (&inner[..bar], Some(inner[bar + 1..].trim().to_string())),
+                None => (inner, None),
+            };
+            let target = target.trim();
+            if !target.is_empty() && !target.contains('[') {
+                links.push(WikiLink {
+                    target: target.to_string(),
+                    label: label.filter(|l| !l.is_empty()),
+                    start: offset + open,
+                    end: offset + close + 2,
+                });
+            }
+            rest = close + 2;
+        }
+    }
+    links
+}
+
+pub fn tags(content: &str) -> Vec<Tag> {
+    let mut tags = Vec::new();
+    for (offset, line) in prose_lines(content) {
+        if heading(line).is_some() {
+            continue;
+        }
+        let mut previous = None;
+        for (i, c) in line.char_indices() {
+            let at_boundary = previous.map(|p: char| p.is_whitespace()).unwrap_or(true);
+            previous = Some(c);
+            if c != '#' || !at_boundary {
+                continue;
+            }
+            let name = line[i + 1..]
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '/'))
+                .next()
+                .unwrap_or("")
+                .trim_end_matches('/');
+            if name.is_empty() || name.chars().all(|c| c.is_ascii_digit()) {
+                continue;
+            }
+            tags.push(Tag {
+                name: name.to_string(),
+                start: offset + i,
+                end: offset + i + 1 + name.len(),
+            });
+        }
+    }
+    tags
+}
+
+pub fn headings(content: &str) -> Vec<Heading> {
+    prose_lines(content)
+        .filter_map(|(offset, line)| {
+            heading(line).map(|(level, title)| Heading {
+                level,
+                title: title.to_string(),
+                start: offset,
+            })
+        })
+        .collect()
+}
+
+/// Title of a note: its first heading, or its name without extension.
+pub fn title(name: &str, content: &str) -> String {
+    headings(content)
+        .into_iter()
+        .next()
+        .map(|h| h.title)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| {
+            let stem = stem(name);
+            stem.rsplit('/').next().unwrap_or(stem).to_string()
+        })
+}
+
+/// Name of a note without its Markdown/text extension.
+pub fn stem(name: &str) -> &str {
+    for extension in &[".md", ".markdown", ".txt"] {
+        if let Some(stem) = name.strip_suffix(extension) {
+            return stem;
+        }
+    }
+    name
+}
+
+/// Finds the note a wiki link `target` points to among `names`. A target
+/// matches a note's full name, its name without extension, or its file name
+/// without extension, ignoring case.
+pub fn resolve<'a, I>(target: &str, names: I) -> Option<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let target = target.split('#').next().unwrap_or(target).trim();
+    let target = target.to_lowercase();
+    let mut fallback = None;
+    for name in names {
+        let stem = stem(name);
+        if name.to_lowercase() == target || stem.to_lowercase() == target {
+            return Some(name);
+        }
+        let base = stem.rsplit('/').next().unwrap_or(stem);
+        if fallback.is_none() && base.to_lowercase() == target {
+            fallback = Some(name);
+        }
+    }
+    fallback
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    if level == 0 || level > 6 {
+        return None;
+    }
+    let rest = &line[level..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((level, rest.trim().trim_end_matches('#').trim()))
+}
+
+/// Lines outside fenced code blocks, with their byte offsets.
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    let mut fence: Option<&str> = None;
+    content.split_inclusive('\n').filter_map(move |raw| {
+        let line_offset = offset;
+        offset += raw.len();
+        let line = raw.trim_end_matches(&['\r', '\n'][..]);
+        let trimmed = line.trim_start();
+        match fence {
+            Some(f) => {
+                if trimmed.starts_with(f) {
+                    fence = None;
+                }
+                None
+            }
+            None if trimmed.starts_with("```") => {
+                fence = Some("```");
+                None
+            }
+            None if trimmed.starts_with("~~~") => {
+                fence = Some("~~~");
+                None
+            }
+            None => Some((line_offset, line)),
+        }
+    })
+}
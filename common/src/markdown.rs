@@ -0,0 +1,212 @@
+use pulldown_cmark::escape::{escape_href, escape_html};
+use pulldown_cmark::{html, CodeBlockKind, CowStr, Event, Options, Parser, Tag};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::SyntaxSet;
+
+use crate::links;
+
+const THEME: &str = "InspiredGitHub";
+
+/// Maps a wiki link target to the URL it should point to.
+pub type ResolveLink<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+struct Highlighter {
+    syntax_set: SyntaxSet,
+    theme: Theme,
+}
+
+thread_local! {
+    static HIGHLIGHTER: Highlighter = Highlighter {
+        syntax_set: SyntaxSet::load_defaults_newlines(),
+        theme: ThemeSet::load_defaults().themes.remove(THEME).expect("load default theme"),
+    };
+}
+
+/// Renders Markdown (CommonMark with GFM tables, task lists, strikethrough and
+/// footnotes) to HTML. Every top-level block is preceded by an empty
+/// `<div data-line="N">` marker holding its 1-based source line, which is used
+/// to keep the preview scrolled along with the editor. Wiki links are rendered
+/// as `<span class="wikilink">`. Raw HTML in the source is shown as text, so
+/// that a note cannot run script wherever it is displayed.
+pub fn render(source: &str) -> String {
+    render_events(source, true, None)
+}
+
+/// Renders Markdown like [`render`] without line markers. Wiki links become
+/// anchors to the URL returned by `resolve`, or a `missing` span when it
+/// returns `None`.
+pub fn render_with_links(source: &str, resolve: ResolveLink) -> String {
+    render_events(source, false, Some(resolve))
+}
+
+/// Destinations of all links and images in `source`.
+pub fn link_destinations(source: &str) -> Vec<String> {
+    Parser::new_ext(source, options())
+        .filter_map(|event| match event {
+            Event::Start(Tag::Link(_, dest, _)) | Event::Start(Tag::Image(_, dest, _)) => {
+                Some(dest.to_string())
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn options() -> Options {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_FOOTNOTES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+    options
+}
+
+fn render_events(source: &str, line_markers: bool, resolve: Option<ResolveLink>) -> String {
+    let line_starts = source
+        .match_indices('\n')
+        .map(|(i, _)| i + 1)
+        .collect::<Vec<_>>();
+    let line_of = |offset: usize| match line_starts.binary_search(&offset) {
+        Ok(i) => i + 2,
+        Err(i) => i + 1,
+    };
+    let marker = |offset: usize| {
+        if line_markers {
+            Some(line_marker(line_of(offset)))
+        } else {
+            None
+        }
+    };
+
+    let mut events = Vec::new();
+    let mut depth = 0;
+    let mut code_block: Option<(Option<String>, String)> = None;
+    // pulldown-cmark splits `[[target]]` into several text events, so text is
+    // buffered until the next non-text event before looking for wiki links.
+    let mut text = String::new();
+    for (event, range) in Parser::new_ext(source, options()).into_offset_iter() {
+        if let Event::Text(t) = &event {
+            match code_block.as_mut() {
+                Some((_, code)) => code.push_str(t),
+                None => text.push_str(t),
+            }
+            continue;
+        }
+        flush_text(&mut text, &mut events, resolve);
+        match event {
+            Event::Start(Tag::CodeBlock(kind)) => {
+                if depth == 0 {
+                    events.extend(marker(range.start));
+                }
+                let lang = match kind {
+                    CodeBlockKind::Fenced(lang) if !lang.is_empty() => Some(lang.to_string()),
+                    _ => None,
+                };
+                code_block = Some((lang, String::new()));
+                depth += 1;
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                depth -= 1;
+                if let Some((lang, code)) = code_block.take() {
+                    events.push(Event::Html(CowStr::from(highlight(lang.as_deref(), &code))));
+                }
+            }
+            Event::Start(tag) => {
+                if depth == 0 {
+                    events.extend(marker(range.start));
+                }
+                depth += 1;
+                events.push(Event::Start(safe_link(tag)));
+            }
+            Event::End(tag) => {
+                depth -= 1;
+                events.push(Event::End(safe_link(tag)));
+            }
+            Event::Html(html) => {
+                if depth == 0 {
+                    events.extend(marker(range.start));
+                }
+                events.push(Event::Text(html));
+            }
+            Event::Rule if depth == 0 => {
+                events.extend(marker(range.start));
+                events.push(Event::Rule);
+            }
+            e => events.push(e),
+        }
+    }
+    flush_text(&mut text, &mut events, resolve);
+
+    let mut output = String::with_capacity(source.len() * 3 / 2);
+    html::push_html(&mut output, events.into_iter());
+    output
+}
+
+fn flush_text<'a>(text: &mut String, events: &mut Vec<Event<'a>>, resolve: Option<ResolveLink>) {
+    if text.is_empty() {
+        return;
+    }
+    let mut rest = 0;
+    for link in links::wiki_links(text) {
+        if link.start > rest {
+            events.push(Event::Text(CowStr::from(
+                text[rest..link.start].to_string(),
+            )));
+        }
+        let label = escape(link.text());
+        let html = match resolve.and_then(|r| r(&link.target)) {
+            Some(url) => {
+                let mut href = String::new();
+                escape_href(&mut href, &url).expect("escape href");
+                format!("<a class=\"wikilink\" href=\"{}\">{}</a>", href, label)
+            }
+            None if resolve.is_some() => {
+                format!("<span class=\"wikilink missing\">{}</span>", label)
+            }
+            None => format!("<span class=\"wikilink\">{}</span>", label),
+        };
+        events.push(Event::Html(CowStr::from(html)));
+        rest = link.end;
+    }
+    if rest < text.len() {
+        events.push(Event::Text(CowStr::from(text[rest..].to_string())));
+    }
+    text.clear();
+}
+
+/// `tag` with links and images to `javascript:` and similar URLs pointed
+/// nowhere.
+fn safe_link(tag: Tag) -> Tag {
+    let is_script = |dest: &str| {
+        let scheme = dest.trim_start().split(':').next().unwrap_or("");
+        dest.contains(':')
+            && ["javascript", "vbscript", "data"]
+                .iter()
+                .any(|s| scheme.eq_ignore_ascii_case(s))
+    };
+    match tag {
+        Tag::Link(kind, dest, title) if is_script(&dest) => Tag::Link(kind, "#".into(), title),
+        Tag::Image(kind, dest, title) if is_script(&dest) => Tag::Image(kind, "#".into(), title),
+        tag => tag,
+    }
+}
+
+fn line_marker<'a>(line: usize) -> Event<'a> {
+    Event::Html(CowStr::from(format!("<div data-line=\"{}\"></div>", line)))
+}
+
+fn highlight(lang: Option<&str>, code: &str) -> String {
+    HIGHLIGHTER.with(|h| {
+        let syntax = lang
+            .and_then(|l| h.syntax_set.find_syntax_by_token(l))
+            .unwrap_or_else(|| h.syntax_set.find_syntax_plain_text());
+        highlighted_html_for_string(code, &h.syntax_set, syntax, &h.theme)
+            .unwrap_or_else(|_| format!("<pre><code>{}</code></pre>", escape(code)))
+    })
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::new();
+    escape_html(&mut escaped, s).expect("escape html");
+    escaped
+}
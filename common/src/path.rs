@@ -0,0 +1,107 @@
+//! Percent-encoding of note names in API URLs. The frontend, the command line
+//! tool and the server's router all go through these functions, so that names
+//! with spaces, `#`, `?`, `%` or non-ASCII characters arrive unchanged.
+
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Everything but the unreserved characters of RFC 3986 is escaped.
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Encodes a note name for a URL path, keeping the `/` between directories.
+pub fn encode(name: &str) -> String {
+    name.split('/')
+        .map(encode_component)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Encodes a single path segment or query value.
+pub fn encode_component(value: &str) -> String {
+    utf8_percent_encode(value, COMPONENT).to_string()
+}
+
+/// Decodes a path encoded with [`encode`]. Returns `None` if it doesn't
+/// decode to UTF-8 or if any directory in it, escaped or not, is `..`.
+pub fn decode(path: &str) -> Option<String> {
+    let decoded = path
+        .split('/')
+        .map(decode_component)
+        .collect::<Option<Vec<_>>>()?
+        .join("/");
+    if decoded.split('/').any(|s| s == "..") {
+        return None;
+    }
+    Some(decoded)
+}
+
+/// Decodes a single path segment or query value. Returns `None` if it
+/// doesn't decode to UTF-8.
+pub fn decode_component(value: &str) -> Option<String> {
+    percent_decode_str(value)
+        .decode_utf8()
+        .ok()
+        .map(|v| v.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for name in &[
+            "todo.md",
+            "inbox/scratch 1.md",
+            "a#b?c%d&e=f+g.md",
+            "メモ/日記.md",
+            "~user/-_.md",
+        ] {
+            let encoded = encode(name);
+            assert!(encoded.is_ascii() && !encoded.contains(|c| " #?&=+".contains(c)));
+            assert_eq!(decode(&encoded).as_deref(), Some(*name));
+        }
+        assert_eq!(encode("inbox/a b.md"), "inbox/a%20b.md");
+        assert_eq!(encode("100%.md"), "100%25.md");
+    }
+
+    #[test]
+    fn slashes_in_components_are_escaped() {
+        assert_eq!(encode_component("a/b"), "a%2Fb");
+        assert_eq!(decode_component("a%2Fb").as_deref(), Some("a/b"));
+        assert_eq!(decode("dir/a%2Fb.md").as_deref(), Some("dir/a/b.md"));
+    }
+
+    #[test]
+    fn invalid_percent_sequences_are_kept_literally() {
+        assert_eq!(decode_component("100%").as_deref(), Some("100%"));
+        assert_eq!(decode_component("%G1").as_deref(), Some("%G1"));
+        assert_eq!(decode_component("%4").as_deref(), Some("%4"));
+        assert_eq!(decode("a/%zz.md").as_deref(), Some("a/%zz.md"));
+    }
+
+    #[test]
+    fn non_utf8_does_not_decode() {
+        assert_eq!(decode_component("%FF"), None);
+        assert_eq!(decode_component("%C3"), None);
+        assert_eq!(decode("dir/%E3%83.md"), None);
+    }
+
+    #[test]
+    fn parent_directories_do_not_decode() {
+        for path in &[
+            "..",
+            "../secret.md",
+            "a/../../b.md",
+            "a/%2E%2E/b.md",
+            "..%2Fsecret.md",
+        ] {
+            assert_eq!(decode(path), None, "{}", path);
+        }
+        assert_eq!(decode("..a/b...md").as_deref(), Some("..a/b...md"));
+        assert_eq!(decode_component("..").as_deref(), Some(".."));
+    }
+}
@@ -15,3 +15,56 @@ export function set_editable(editable) {
         readOnly: !editable,
     });
 }
+
+// The Monaco instance is created after the wasm app has started, so defer
+// listener registration until it exists.
+function withEditor(f) {
+    if (window.editor) {
+        f(window.editor);
+    } else {
+        setTimeout(() => withEditor(f), 50);
+    }
+}
+
+export function on_change(callback) {
+    withEditor(editor => editor.onDidChangeModelContent(() => callback(editor.getValue())));
+}
+
+export function on_scroll(callback) {
+    withEditor(editor => editor.onDidScrollChange(() => {
+        let ranges = editor.getVisibleRanges();
+        callback(ranges.length > 0 ? ranges[0].startLineNumber : 1);
+    }));
+}
+
+export function on_files(callback) {
+    withEditor(editor => {
+        let node = editor.getDomNode();
+        let handle = (e, files) => {
+            if (!files || files.length === 0) {
+                return;
+            }
+            e.preventDefault();
+            e.stopPropagation();
+            for (let i = 0; i < files.length; i++) {
+                callback(files[i]);
+            }
+        };
+        node.addEventListener('paste', e => handle(e, e.clipboardData && e.clipboardData.files), true);
+        node.addEventListener('dragover', e => {
+            if (e.dataTransfer && Array.from(e.dataTransfer.types).includes('Files')) {
+                e.preventDefault();
+            }
+        }, true);
+        node.addEventListener('drop', e => handle(e, e.dataTransfer && e.dataTransfer.files), true);
+    });
+}
+
+export function insert_text(text) {
+    window.editor.executeEdits('scrapnote', [{
+        range: window.editor.getSelection(),
+        text: text,
+        forceMoveMarkers: true,
+    }]);
+    window.editor.focus();
+}
@@ -0,0 +1,29 @@
+import { has_ipc } from './ipc.js';
+
+let listeners = [];
+let source = null;
+
+function dispatch(data) {
+    listeners.forEach(listener => listener(data));
+}
+
+// Commands pushed by the `webview` binary, e.g. to open a note passed to a
+// later launch. Commands sent before this is called were queued by the
+// window.
+export function on_command(callback) {
+    let pending = window.scrapnote_pending_commands || [];
+    window.scrapnote_pending_commands = [];
+    window.scrapnote_ipc_command = callback;
+    pending.forEach(callback);
+}
+
+export function on_file_event(callback) {
+    listeners.push(callback);
+    if (has_ipc()) {
+        // Pushed by the `webview` binary through the native bridge.
+        window.scrapnote_ipc_event = dispatch;
+    } else if (!source) {
+        source = new EventSource('/api/events');
+        source.onmessage = e => dispatch(e.data);
+    }
+}
@@ -0,0 +1,54 @@
+// When running inside the webview window, API requests go through the native
+// bridge registered by the `webview` binary instead of HTTP. The bridge is
+// installed as a fetch shim so that the rest of the app does not need to know
+// which transport is in use.
+
+export function has_ipc() {
+    return typeof window.scrapnote_ipc === 'function';
+}
+
+function encode(buffer) {
+    let bytes = new Uint8Array(buffer);
+    let binary = '';
+    for (let i = 0; i < bytes.length; i++) {
+        binary += String.fromCharCode(bytes[i]);
+    }
+    return btoa(binary);
+}
+
+function decode(base64) {
+    let binary = atob(base64);
+    let bytes = new Uint8Array(binary.length);
+    for (let i = 0; i < binary.length; i++) {
+        bytes[i] = binary.charCodeAt(i);
+    }
+    return bytes;
+}
+
+export function install_fetch_shim() {
+    if (!has_ipc()) {
+        return;
+    }
+    let fetch = window.fetch.bind(window);
+    window.fetch = async function (input, init) {
+        let request = new Request(input, init);
+        let url = new URL(request.url);
+        if (url.origin !== location.origin || !url.pathname.startsWith('/api/')) {
+            return fetch(input, init);
+        }
+        let request_body = request.method === 'GET' || request.method === 'HEAD'
+            ? ''
+            : encode(await request.arrayBuffer());
+        let response = await window.scrapnote_ipc({
+            method: request.method,
+            path: url.pathname + url.search,
+            content_type: request.headers.get('Content-Type'),
+            body: request_body,
+        });
+        let headers = response.content_type ? { 'Content-Type': response.content_type } : {};
+        let body_bytes = response.status === 204 || response.status === 304
+            ? null
+            : decode(response.body);
+        return new Response(body_bytes, { status: response.status, headers: headers });
+    };
+}
@@ -0,0 +1,29 @@
+// Window title and size. Inside the webview window they are passed on to the
+// `webview` binary, which sets the native title and remembers the size.
+
+function has_window_binding() {
+    return typeof window.scrapnote_window === 'function';
+}
+
+export function set_title(title) {
+    if (document.title === title) {
+        return;
+    }
+    document.title = title;
+    if (has_window_binding()) {
+        window.scrapnote_window({ SetTitle: title });
+    }
+}
+
+export function install_resize_listener() {
+    if (!has_window_binding()) {
+        return;
+    }
+    let timer = null;
+    window.addEventListener('resize', () => {
+        clearTimeout(timer);
+        timer = setTimeout(() => window.scrapnote_window({
+            Resized: { width: window.innerWidth, height: window.innerHeight },
+        }), 500);
+    });
+}
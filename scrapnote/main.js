@@ -1,15 +1,10 @@
 import init, { run_app } from './pkg/scrapnote.js';
 
 function setUpMonacoEditor() {
-    require.config({ paths: { 'vs': 'https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min/vs' }});
-    window.MonacoEnvironment = { getWorkerUrl: () => proxy };
-    let proxy = URL.createObjectURL(new Blob([`
-        self.MonacoEnvironment = {
-            baseUrl: 'https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min'
-        };
-        importScripts('https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min/vs/base/worker/workerMain.min.js');
-    `], { type: 'text/javascript' }));
-    require(["vs/editor/editor.main"], function () {
+    window.MonacoEnvironment = { getWorkerUrl: () => './vendor/monaco/editor.worker.js' };
+    // Monaco is an ES module of its own rather than part of this bundle.
+    let url = new URL('./vendor/monaco/editor.js', document.baseURI).href;
+    import(url).then(function (monaco) {
         window.editor = monaco.editor.create(document.getElementById('editor'), {
             automaticLayout: true,
             language: 'markdown',
@@ -17,7 +12,7 @@ function setUpMonacoEditor() {
             lineNumbers: 'off',
             minimap: { enabled: false },
             readOnly: true,
-            fontFamily: 'Noto Sans JP',
+            fontFamily: '"Noto Sans JP", sans-serif',
             folding: false,
             lineDecorationsWidth: 0,
             lineNumbersMinChars: 0,
@@ -29,6 +24,22 @@ function setUpMonacoEditor() {
             document.getElementById('editor')
                 .dispatchEvent(new KeyboardEvent('keypress', {key: 'Escape'}));
         }, '!suggestWidgetVisible');
+        window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyCode.KeyS, function() {
+            document.getElementById('editor')
+                .dispatchEvent(new KeyboardEvent('keypress', {key: 'Save'}));
+        });
+        window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyD, function() {
+            document.getElementById('editor')
+                .dispatchEvent(new KeyboardEvent('keypress', {key: 'Discard'}));
+        });
+        window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyV, function() {
+            document.getElementById('editor')
+                .dispatchEvent(new KeyboardEvent('keypress', {key: 'TogglePreview'}));
+        });
+        window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyE, function() {
+            document.getElementById('editor')
+                .dispatchEvent(new KeyboardEvent('keypress', {key: 'OpenExternal'}));
+        });
     });
 
 }
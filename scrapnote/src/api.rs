@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::fmt;
+use yew::callback::Callback;
+use yew::format::Nothing;
+use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+
+use common::api::Endpoint;
+
+thread_local! {
+    static VAULT: RefCell<String> = RefCell::new(common::DEFAULT_VAULT.to_string());
+}
+
+/// The vault that requests made through [`fetch`] work on.
+pub fn vault() -> String {
+    VAULT.with(|v| v.borrow().clone())
+}
+
+pub fn set_vault(name: &str) {
+    VAULT.with(|v| *v.borrow_mut() = name.to_string());
+}
+
+/// An error status returned by the server.
+#[derive(Debug)]
+pub struct StatusError(pub u16);
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "server responded with {}", self.0)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+/// Whether `error` is a `409 Conflict` from the server.
+pub fn is_conflict(error: &anyhow::Error) -> bool {
+    matches!(error.downcast_ref::<StatusError>(), Some(StatusError(409)))
+}
+
+/// Calls `endpoint` on the active vault of the server that served the page
+/// and passes the parsed response to `callback`.
+pub fn fetch<E>(endpoint: E, callback: Callback<Result<E::Response, anyhow::Error>>) -> FetchTask
+where
+    E: Endpoint,
+    E::Response: 'static,
+{
+    let handler = move |response: Response<Result<Vec<u8>, anyhow::Error>>| {
+        let status = response.status();
+        let result = response.into_body().and_then(|body| {
+            if !status.is_success() {
+                return Err(StatusError(status.as_u16()).into());
+            }
+            serde_json::from_slice(&body).map_err(anyhow::Error::from)
+        });
+        callback.emit(result);
+    };
+    let builder = Request::builder()
+        .method(E::METHOD.as_str())
+        .uri(endpoint.path_in(&vault()));
+    let task = match endpoint.body() {
+        Some(body) => FetchService::fetch_binary(
+            builder
+                .header("Content-Type", body.content_type)
+                .body(Ok(body.data))
+                .expect("build request"),
+            handler.into(),
+        ),
+        None => FetchService::fetch_binary(
+            builder.body(Nothing).expect("build request"),
+            handler.into(),
+        ),
+    };
+    task.expect("fetch")
+}
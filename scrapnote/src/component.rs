@@ -1,2 +1,3 @@
 pub mod editor;
+pub mod preview;
 pub mod selector;
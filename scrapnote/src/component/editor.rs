@@ -1,10 +1,22 @@
+use std::time::Duration;
 use wasm_bindgen::prelude::*;
 
-use yew::format::Json;
 use yew::prelude::*;
-use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::services::fetch::FetchTask;
+use yew::services::reader::{File, FileData, ReaderService, ReaderTask};
+use yew::services::timeout::{TimeoutService, TimeoutTask};
 
-use common::{GetFileContentResponse, SaveFileContentRequest};
+use common::{
+    api, FileEvent, FileEventKind, GetFileContentResponse, OpenExternalResponse,
+    SaveFileContentRequest, SaveFileContentResponse, UploadAttachmentResponse,
+};
+
+use crate::component::preview::Preview;
+use crate::draft::{self, Draft};
+
+/// Time without typing after which changes are saved, and kept as a draft
+/// in local storage.
+const AUTOSAVE_DELAY: Duration = Duration::from_millis(1000);
 
 #[wasm_bindgen(module = "/js/ace.js")]
 extern "C" {
@@ -12,6 +24,20 @@ extern "C" {
     fn set_value(value: String);
     fn focus();
     fn set_editable(editable: bool);
+    fn on_change(callback: &Closure<dyn FnMut(String)>);
+    fn on_scroll(callback: &Closure<dyn FnMut(u32)>);
+    fn on_files(callback: &Closure<dyn FnMut(File)>);
+    fn insert_text(text: String);
+}
+
+#[wasm_bindgen(module = "/js/events.js")]
+extern "C" {
+    fn on_file_event(callback: &Closure<dyn FnMut(String)>);
+}
+
+#[wasm_bindgen(module = "/js/window.js")]
+extern "C" {
+    fn set_title(title: String);
 }
 
 pub struct Editor {
@@ -20,12 +46,88 @@ pub struct Editor {
     fetch_task: Option<FetchTask>,
     save_task: Option<FetchTask>,
     editor_ref: NodeRef,
+    content: String,
+    top_line: u32,
+    show_preview: bool,
+    reader_tasks: Vec<ReaderTask>,
+    upload_tasks: Vec<FetchTask>,
+    /// Content of the file as last loaded from or saved to disk.
+    loaded_content: Option<String>,
+    /// Content of the file when it was opened, which discarding returns to.
+    opened_content: Option<String>,
+    /// Modification time of the file as last loaded or saved. Saves are
+    /// refused by the server if the file was changed since, so that changes
+    /// made elsewhere are not overwritten.
+    modified: Option<u64>,
+    /// Whether the file was autosaved since the last explicit save. Autosaves
+    /// skip the server-side tidy-up of scratch notes.
+    autosaved: bool,
+    external_change: Option<ExternalChange>,
+    /// Whether the file is open in an external editor. The editor is
+    /// read-only meanwhile and follows the changes made there.
+    editing_externally: bool,
+    external_task: Option<FetchTask>,
+    autosave_task: Option<TimeoutTask>,
+    /// Save of the edits left in a file that was closed before they were
+    /// autosaved.
+    flush_task: Option<FetchTask>,
+    recoverable_draft: Option<Draft>,
+    _on_change: Closure<dyn FnMut(String)>,
+    _on_scroll: Closure<dyn FnMut(u32)>,
+    _on_files: Closure<dyn FnMut(File)>,
+    _on_file_event: Closure<dyn FnMut(String)>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SaveMode {
+    /// Save and hand the focus back to the command line.
+    Close,
+    /// Save and keep editing.
+    Stay,
+    /// Save in the background while typing.
+    Autosave,
+    /// Save and open the file in the external editor.
+    OpenExternal,
+}
+
+/// A change made to the open file outside the editor while it has unsaved
+/// edits.
+enum ExternalChange {
+    /// The content and modification time of the file on disk.
+    Changed(String, Option<u64>),
+    Removed,
 }
 
 pub enum Msg {
     Save,
+    SaveInPlace,
+    /// The saved content and the response to saving it.
+    Saved(SaveMode, String, SaveFileContentResponse),
+    /// Whether the save was refused because the file was changed since it
+    /// was loaded.
+    SaveFailed(SaveMode, bool),
+    SaveConflict(GetFileContentResponse),
+    /// Whether the edits left in the closed file were saved.
+    Flushed(String, bool),
+    Discard,
     Unfocus,
-    ContentLoaded(String),
+    FileOpened(GetFileContentResponse),
+    ContentLoaded(String, Option<u64>),
+    ContentChanged(String),
+    Autosave,
+    RecoverDraft,
+    DiscardDraft,
+    Scrolled(u32),
+    TogglePreview,
+    FileDropped(File),
+    FileRead(FileData, String),
+    AttachmentUploaded(String, String, bool),
+    FileEvent(String),
+    DiskContentLoaded(GetFileContentResponse),
+    ReloadFromDisk,
+    KeepLocal,
+    OpenExternal,
+    EditingExternally(bool),
     None,
 }
 
@@ -35,38 +137,328 @@ pub struct Props {
     pub file_name: Option<String>,
     pub focus: bool,
     pub on_unfocus: Callback<()>,
+    pub on_save: Callback<Option<String>>,
 }
 
 impl Component for Editor {
     type Message = Msg;
     type Properties = Props;
 
+    #[allow(unused_unsafe)]
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let on_change_link = link.clone();
+        let on_change_callback = Closure::wrap(Box::new(move |content| {
+            on_change_link.send_message(Msg::ContentChanged(content))
+        }) as Box<dyn FnMut(String)>);
+        let on_scroll_link = link.clone();
+        let on_scroll_callback = Closure::wrap(Box::new(move |line| {
+            on_scroll_link.send_message(Msg::Scrolled(line))
+        }) as Box<dyn FnMut(u32)>);
+        let on_files_link = link.clone();
+        let on_files_callback = Closure::wrap(Box::new(move |file| {
+            on_files_link.send_message(Msg::FileDropped(file))
+        }) as Box<dyn FnMut(File)>);
+        let on_file_event_link = link.clone();
+        let on_file_event_callback = Closure::wrap(Box::new(move |event| {
+            on_file_event_link.send_message(Msg::FileEvent(event))
+        }) as Box<dyn FnMut(String)>);
+        unsafe {
+            on_change(&on_change_callback);
+            on_scroll(&on_scroll_callback);
+            on_files(&on_files_callback);
+            on_file_event(&on_file_event_callback);
+        }
         Self {
             link,
             props,
             fetch_task: None,
             save_task: None,
             editor_ref: NodeRef::default(),
+            content: String::new(),
+            top_line: 1,
+            show_preview: false,
+            reader_tasks: Vec::new(),
+            upload_tasks: Vec::new(),
+            loaded_content: None,
+            opened_content: None,
+            modified: None,
+            autosaved: false,
+            external_change: None,
+            editing_externally: false,
+            external_task: None,
+            flush_task: None,
+            autosave_task: None,
+            recoverable_draft: None,
+            _on_change: on_change_callback,
+            _on_scroll: on_scroll_callback,
+            _on_files: on_files_callback,
+            _on_file_event: on_file_event_callback,
         }
     }
 
     #[allow(unused_unsafe)]
     fn update(&mut self, msg: Self::Message) -> ShouldRender {
         match msg {
-            Msg::Save => match &self.props.file_name.to_owned() {
-                Some(f) => unsafe { self.save_content(f, get_value()) },
-                None => self.link.send_message(Msg::Unfocus),
+            Msg::Save => match self.props.file_name.to_owned() {
+                Some(f) if self.needs_save() => unsafe {
+                    self.save_content(&f, get_value(), SaveMode::Close);
+                    return true;
+                },
+                _ => self.link.send_message(Msg::Unfocus),
+            },
+            Msg::SaveInPlace => match self.props.file_name.to_owned() {
+                Some(f) if self.needs_save() => unsafe {
+                    self.save_content(&f, get_value(), SaveMode::Stay);
+                    return true;
+                },
+                _ => {}
+            },
+            Msg::Saved(mode, content, response) => unsafe {
+                self.loaded_content = Some(content);
+                self.modified = response.modified;
+                match mode {
+                    SaveMode::OpenExternal => self.link.send_message(Msg::OpenExternal),
+                    SaveMode::Autosave => {
+                        if let Some(f) = &self.props.file_name {
+                            if self.loaded_content.as_ref() == Some(&get_value()) {
+                                draft::clear(f);
+                            }
+                        }
+                    }
+                    SaveMode::Close | SaveMode::Stay => {
+                        if let Some(f) = &self.props.file_name {
+                            if !self.is_dirty() {
+                                draft::clear(f);
+                            }
+                        }
+                        let name = response.name;
+                        if name.as_ref() != self.props.file_name.as_ref() {
+                            self.props.on_save.emit(name);
+                        }
+                        if mode == SaveMode::Close {
+                            self.link.send_message(Msg::Unfocus);
+                        }
+                    }
+                }
+            },
+            Msg::SaveFailed(_, true) => {
+                if let Some(f) = self.props.file_name.to_owned() {
+                    self.get_contents(&f, Msg::SaveConflict);
+                }
+            }
+            Msg::Flushed(f, saved) => {
+                if !saved {
+                    log::warn!("edits to {} were kept as a draft", f);
+                }
+                self.flush_task = None;
+                return false;
+            }
+            Msg::SaveFailed(mode, false) => {
+                if mode == SaveMode::Close {
+                    self.link.send_message(Msg::Unfocus);
+                }
+            }
+            Msg::SaveConflict(response) => {
+                log::info!("file was changed since it was loaded");
+                self.external_change =
+                    Some(ExternalChange::Changed(response.content, response.modified));
+                return true;
+            }
+            Msg::Discard => unsafe {
+                let opened = (
+                    self.props.file_name.to_owned(),
+                    self.opened_content.to_owned(),
+                );
+                if let (Some(f), Some(c)) = opened {
+                    log::info!("discard changes: {}", f);
+                    draft::clear(&f);
+                    self.recoverable_draft = None;
+                    set_value(c.clone());
+                    if self.loaded_content.as_ref() != Some(&c) {
+                        self.save_content(&f, c, SaveMode::Autosave);
+                    }
+                    return true;
+                }
             },
             Msg::Unfocus => {
                 self.props.on_unfocus.emit(());
             }
-            Msg::ContentLoaded(c) => unsafe {
+            Msg::FileOpened(response) => {
+                let file_name = match &self.props.file_name {
+                    Some(f) => f.to_owned(),
+                    None => return false,
+                };
+                let modified = response.modified.unwrap_or(0) as f64;
+                self.editing_externally = response.editing_externally;
+                let content = response.content;
+                self.opened_content = Some(content.clone());
+                let recoverable = draft::load(&file_name)
+                    .filter(|d| d.content != content && d.saved_at > modified);
+                match recoverable {
+                    Some(d) => self.recoverable_draft = Some(d),
+                    None => draft::clear(&file_name),
+                }
+                self.link
+                    .send_message(Msg::ContentLoaded(content, response.modified));
+                return self.recoverable_draft.is_some();
+            }
+            Msg::ContentLoaded(c, modified) => unsafe {
+                self.loaded_content = Some(c.clone());
+                self.modified = modified;
+                self.autosaved = false;
                 set_value(c);
-                set_editable(true);
+                set_editable(!self.editing_externally);
                 if self.props.focus {
                     focus();
                 }
+                if self.external_change.take().is_some() {
+                    return true;
+                }
+            },
+            Msg::ContentChanged(c) => {
+                if self.props.file_name.is_some() && self.loaded_content.as_ref() != Some(&c) {
+                    self.schedule_autosave();
+                }
+                let was_dirty = self.is_dirty();
+                self.content = c;
+                return self.show_preview || was_dirty != self.is_dirty();
+            }
+            Msg::Autosave => unsafe {
+                self.autosave_task = None;
+                let content = get_value();
+                if let Some(f) = self.props.file_name.to_owned() {
+                    if self.loaded_content.as_ref() != Some(&content) {
+                        draft::store(&f, &content);
+                        // Saving now would overwrite the changes made
+                        // elsewhere before the user decided what to keep.
+                        if self.external_change.is_none() && !self.editing_externally {
+                            self.save_content(&f, content, SaveMode::Autosave);
+                            return true;
+                        }
+                    }
+                }
+            },
+            Msg::RecoverDraft => unsafe {
+                if let Some(d) = self.recoverable_draft.take() {
+                    set_value(d.content);
+                }
+                return true;
+            },
+            Msg::DiscardDraft => {
+                if let Some(f) = &self.props.file_name {
+                    draft::clear(f);
+                }
+                self.recoverable_draft = None;
+                return true;
+            }
+            Msg::Scrolled(line) => {
+                self.top_line = line;
+                return self.show_preview;
+            }
+            Msg::TogglePreview => {
+                self.show_preview = !self.show_preview;
+                return true;
+            }
+            Msg::FileDropped(file) => {
+                let content_type = file.type_();
+                let callback = self
+                    .link
+                    .callback(move |data| Msg::FileRead(data, content_type.clone()));
+                self.reader_tasks.push(
+                    ReaderService::new()
+                        .read_file(file, callback)
+                        .expect("read dropped file"),
+                );
+            }
+            Msg::FileRead(data, content_type) => {
+                self.upload_attachment(data, content_type);
+            }
+            Msg::AttachmentUploaded(name, url, is_image) => unsafe {
+                let name = escape_link_label(&name);
+                let link = if is_image {
+                    format!("![{}]({})", name, url)
+                } else {
+                    format!("[{}]({})", name, url)
+                };
+                insert_text(link);
+            },
+            Msg::FileEvent(event) => {
+                let event: FileEvent = match serde_json::from_str(&event) {
+                    Ok(e) => e,
+                    Err(_) => return false,
+                };
+                if event.vault != crate::api::vault() {
+                    return false;
+                }
+                let file_name = match &self.props.file_name {
+                    Some(f) if *f == event.name => f.to_owned(),
+                    _ => return false,
+                };
+                match event.kind {
+                    FileEventKind::Removed => {
+                        self.external_change = Some(ExternalChange::Removed);
+                        return true;
+                    }
+                    FileEventKind::EditingStarted => {
+                        self.link.send_message(Msg::EditingExternally(true))
+                    }
+                    FileEventKind::EditingEnded => {
+                        self.link.send_message(Msg::EditingExternally(false));
+                        self.get_contents(&file_name, Msg::DiskContentLoaded);
+                    }
+                    FileEventKind::Created | FileEventKind::Changed => {
+                        self.get_contents(&file_name, Msg::DiskContentLoaded)
+                    }
+                }
+            }
+            Msg::DiskContentLoaded(response) => unsafe {
+                let c = response.content;
+                if self.loaded_content.as_ref() == Some(&c) {
+                    self.modified = response.modified;
+                    return false;
+                }
+                if self.loaded_content.as_ref() == Some(&get_value()) {
+                    log::info!("reload changed file");
+                    self.link
+                        .send_message(Msg::ContentLoaded(c, response.modified));
+                } else {
+                    self.external_change = Some(ExternalChange::Changed(c, response.modified));
+                    return true;
+                }
+            },
+            Msg::ReloadFromDisk => {
+                if let Some(ExternalChange::Changed(c, modified)) = self.external_change.take() {
+                    self.link.send_message(Msg::ContentLoaded(c, modified));
+                }
+                return true;
+            }
+            Msg::KeepLocal => {
+                // The next save replaces the file as it is now.
+                self.modified = match self.external_change.take() {
+                    Some(ExternalChange::Changed(_, modified)) => modified,
+                    _ => None,
+                };
+                self.schedule_autosave();
+                return true;
+            }
+            Msg::OpenExternal => match self.props.file_name.to_owned() {
+                Some(_) if self.editing_externally => {}
+                Some(f) if self.is_dirty() => unsafe {
+                    self.save_content(&f, get_value(), SaveMode::OpenExternal);
+                },
+                Some(f) => self.open_external(&f),
+                None => {}
+            },
+            Msg::EditingExternally(editing) => unsafe {
+                if editing == self.editing_externally {
+                    return false;
+                }
+                self.editing_externally = editing;
+                if editing {
+                    self.autosave_task = None;
+                }
+                set_editable(!editing);
+                return true;
             },
             Msg::None => {}
         }
@@ -75,70 +467,286 @@ impl Component for Editor {
 
     #[allow(unused_unsafe)]
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let file_changed = props.file_name != self.props.file_name;
+        if file_changed {
+            // Keep the edits that were waiting for the autosave delay, and
+            // save them unless that would overwrite changes made elsewhere.
+            if let (Some(f), Some(_)) = (self.props.file_name.clone(), self.autosave_task.take()) {
+                draft::store(&f, &self.content);
+                if self.external_change.is_none() && !self.editing_externally {
+                    self.flush(f);
+                }
+            }
+            self.reader_tasks.clear();
+            self.upload_tasks.clear();
+            self.external_change = None;
+            self.recoverable_draft = None;
+            self.autosave_task = None;
+            self.opened_content = None;
+            self.editing_externally = false;
+            self.external_task = None;
+        }
         if let Some(ref f) = props.file_name {
-            self.get_contents(&f);
+            if file_changed {
+                self.get_contents(f, Msg::FileOpened);
+            } else {
+                self.get_contents(f, Msg::DiskContentLoaded);
+            }
         }
         self.props = props;
-        false
+        file_changed
+    }
+
+    #[allow(unused_unsafe)]
+    fn rendered(&mut self, _first_render: bool) {
+        let title = match &self.props.file_name {
+            Some(f) if self.is_dirty() => format!("● {} - scrapnote", f),
+            Some(f) => format!("{} - scrapnote", f),
+            None => "scrapnote".to_string(),
+        };
+        unsafe {
+            set_title(title);
+        }
     }
 
     fn view(&self) -> Html {
         html! {
-            <div
-              ref=self.editor_ref.clone()
-              id="editor"
-              onkeypress=self.link.callback(|e: KeyboardEvent| {
-                  match e.key().as_ref() {
-                    "Escape" => Msg::Save,
-                    _ => Msg::None
-                  }
-              })
-            />
+            <div id="editor-area">
+              { self.view_status() }
+              { self.view_recoverable_draft() }
+              { self.view_external_change() }
+              <div id="editor-pane">
+                <div
+                  ref=self.editor_ref.clone()
+                  id="editor"
+                  onkeypress=self.link.callback(|e: KeyboardEvent| {
+                      match e.key().as_ref() {
+                        "Escape" => Msg::Save,
+                        "Save" => Msg::SaveInPlace,
+                        "Discard" => Msg::Discard,
+                        "TogglePreview" => Msg::TogglePreview,
+                        "OpenExternal" => Msg::OpenExternal,
+                        _ => Msg::None
+                      }
+                  })
+                />
+                <Preview
+                  content=self.content.clone()
+                  visible=self.show_preview
+                  line=self.top_line
+                />
+              </div>
+            </div>
         }
     }
 }
 
 impl Editor {
-    fn get_contents(&mut self, file_name: &str) {
-        let port = yew::utils::window().location().port();
-        let request = Request::get(format!(
-            "http://127.0.0.1:{}/api/file/{}",
-            port.unwrap(),
-            file_name
-        ))
-        .body(yew::format::Nothing)
-        .unwrap();
+    /// Whether the buffer differs from the content on disk.
+    fn is_dirty(&self) -> bool {
+        match &self.loaded_content {
+            Some(c) => *c != self.content,
+            None => false,
+        }
+    }
 
+    /// Whether saving has to reach the server. Besides unsaved edits, the
+    /// server tidies up scratch notes on explicit saves, which an autosave or
+    /// an untouched empty note still need.
+    fn needs_save(&self) -> bool {
+        self.is_dirty() || self.autosaved || self.content.is_empty()
+    }
+
+    fn view_status(&self) -> Html {
+        let file_name = match &self.props.file_name {
+            Some(f) => f,
+            None => return html! {},
+        };
+        let dirty = self.is_dirty();
+        let changed = matches!(&self.opened_content, Some(c) if *c != self.content);
+        html! {
+            <div id="editor-status" class=if dirty { "dirty" } else { "" }>
+              <span class="file-name">{ file_name }</span>
+              <button class="close" onclick=self.link.callback(|_| Msg::Save)>{ "Done" }</button>
+              {
+                  if self.editing_externally {
+                      html! { <span class="external">{ "Open in external editor" }</span> }
+                  } else {
+                      html! {
+                        <button onclick=self.link.callback(|_| Msg::OpenExternal)>{ "Open in editor" }</button>
+                      }
+                  }
+              }
+              {
+                  if dirty {
+                      html! {
+                        <>
+                          <span class="dirty-marker" title="Unsaved changes">{ "●" }</span>
+                          <button onclick=self.link.callback(|_| Msg::SaveInPlace)>{ "Save" }</button>
+                        </>
+                      }
+                  } else {
+                      html! {}
+                  }
+              }
+              {
+                  if changed {
+                      html! {
+                        <button onclick=self.link.callback(|_| Msg::Discard)>{ "Discard" }</button>
+                      }
+                  } else {
+                      html! {}
+                  }
+              }
+            </div>
+        }
+    }
+
+    fn view_recoverable_draft(&self) -> Html {
+        match &self.recoverable_draft {
+            Some(_) => html! {
+                <div class="notice">
+                  <span>{ "There is an unsaved draft of this note that is newer than the file." }</span>
+                  <button onclick=self.link.callback(|_| Msg::RecoverDraft)>{ "Recover" }</button>
+                  <button onclick=self.link.callback(|_| Msg::DiscardDraft)>{ "Discard" }</button>
+                </div>
+            },
+            None => html! {},
+        }
+    }
+
+    fn view_external_change(&self) -> Html {
+        match &self.external_change {
+            Some(ExternalChange::Changed(..)) => html! {
+                <div class="notice">
+                  <span>{ "This note was changed outside scrapnote." }</span>
+                  <button onclick=self.link.callback(|_| Msg::ReloadFromDisk)>{ "Reload" }</button>
+                  <button onclick=self.link.callback(|_| Msg::KeepLocal)>{ "Keep mine" }</button>
+                </div>
+            },
+            Some(ExternalChange::Removed) => html! {
+                <div class="notice">
+                  <span>{ "This note was deleted outside scrapnote." }</span>
+                  <button onclick=self.link.callback(|_| Msg::KeepLocal)>{ "Dismiss" }</button>
+                </div>
+            },
+            None => html! {},
+        }
+    }
+
+    fn get_contents(&mut self, file_name: &str, on_loaded: fn(GetFileContentResponse) -> Msg) {
+        let endpoint = api::GetFile {
+            name: file_name.to_string(),
+            create_missing: true,
+        };
         let callback = self.link.callback(
-            |response: Response<Json<Result<GetFileContentResponse, anyhow::Error>>>| {
-                let Json(data) = response.into_body();
-                if let Ok(data) = data {
-                    Msg::ContentLoaded(data.content)
-                } else {
-                    Msg::None
-                }
+            move |result: Result<GetFileContentResponse, anyhow::Error>| match result {
+                Ok(data) => on_loaded(data),
+                Err(_) => Msg::None,
             },
         );
         drop(self.fetch_task.take());
-        self.fetch_task =
-            Some(FetchService::fetch(request, callback).expect("fetch file contents"));
-    }
-
-    fn save_content(&mut self, file_name: &str, content: String) {
-        let port = yew::utils::window().location().port();
-        let body = SaveFileContentRequest { content };
-        let request = Request::post(format!(
-            "http://127.0.0.1:{}/api/file/{}",
-            port.unwrap(),
-            file_name
-        ))
-        .body(Json(&body))
-        .unwrap();
-
-        let callback = self
-            .link
-            .callback(|_: Response<Result<String, anyhow::Error>>| Msg::Unfocus);
+        self.fetch_task = Some(crate::api::fetch(endpoint, callback));
+    }
+
+    fn save_content(&mut self, file_name: &str, content: String, mode: SaveMode) {
+        // Scratch notes are only renamed on explicit saves, which would
+        // change the name of the file being opened.
+        let autosave = mode == SaveMode::Autosave || mode == SaveMode::OpenExternal;
+        self.autosave_task = None;
+        self.autosaved = autosave;
+        let endpoint = api::SaveFile {
+            name: file_name.to_string(),
+            request: SaveFileContentRequest {
+                content: content.clone(),
+                autosave,
+                create_new: false,
+                expected_modified: self.modified,
+            },
+        };
+        let callback = self.link.callback(
+            move |result: Result<SaveFileContentResponse, anyhow::Error>| match result {
+                Ok(data) => Msg::Saved(mode, content.clone(), data),
+                Err(e) => Msg::SaveFailed(mode, crate::api::is_conflict(&e)),
+            },
+        );
         drop(self.save_task.take());
-        self.save_task = Some(FetchService::fetch(request, callback).expect("save content"));
+        self.save_task = Some(crate::api::fetch(endpoint, callback));
+    }
+
+    /// Saves the edits to `file_name`, which was closed before they were
+    /// autosaved. Another file is open by the time the server answers, so
+    /// the response is only logged.
+    fn flush(&mut self, file_name: String) {
+        let endpoint = api::SaveFile {
+            name: file_name.clone(),
+            request: SaveFileContentRequest {
+                content: self.content.clone(),
+                autosave: true,
+                create_new: false,
+                expected_modified: self.modified,
+            },
+        };
+        let callback = self.link.callback(
+            move |result: Result<SaveFileContentResponse, anyhow::Error>| {
+                Msg::Flushed(file_name.clone(), result.is_ok())
+            },
+        );
+        self.flush_task = Some(crate::api::fetch(endpoint, callback));
+    }
+
+    fn schedule_autosave(&mut self) {
+        self.autosave_task = Some(TimeoutService::spawn(
+            AUTOSAVE_DELAY,
+            self.link.callback(|_| Msg::Autosave),
+        ));
+    }
+
+    fn open_external(&mut self, file_name: &str) {
+        let endpoint = api::OpenExternal {
+            name: file_name.to_string(),
+        };
+        let callback =
+            self.link
+                .callback(
+                    move |result: Result<OpenExternalResponse, anyhow::Error>| match result {
+                        Ok(_) => Msg::EditingExternally(true),
+                        Err(e) => {
+                            log::error!("open in external editor: {}", e);
+                            Msg::None
+                        }
+                    },
+                );
+        self.external_task = Some(crate::api::fetch(endpoint, callback));
+    }
+
+    fn upload_attachment(&mut self, data: FileData, content_type: String) {
+        let name = data.name;
+        let is_image = content_type.starts_with("image/");
+        let endpoint = api::UploadAttachment {
+            name: Some(name.clone()),
+            content_type,
+            data: data.content,
+        };
+        let callback = self.link.callback(
+            move |result: Result<UploadAttachmentResponse, anyhow::Error>| match result {
+                Ok(data) => Msg::AttachmentUploaded(name.clone(), data.url, is_image),
+                Err(_) => Msg::None,
+            },
+        );
+        self.upload_tasks
+            .push(crate::api::fetch(endpoint, callback));
+    }
+}
+
+/// `name` with the characters that would end a Markdown link label escaped.
+fn escape_link_label(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    for c in name.chars() {
+        if matches!(c, '\\' | '[' | ']') {
+            result.push('\\');
+        }
+        result.push(c);
     }
+    result
 }
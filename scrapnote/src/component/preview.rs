@@ -0,0 +1,103 @@
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yew::web_sys::HtmlElement;
+
+use common::markdown;
+
+pub struct Preview {
+    props: Props,
+    preview_ref: NodeRef,
+    rendered: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Properties)]
+pub struct Props {
+    pub content: String,
+    pub visible: bool,
+    /// First source line visible in the editor.
+    pub line: u32,
+}
+
+impl Component for Preview {
+    type Message = ();
+    type Properties = Props;
+
+    fn create(props: Self::Properties, _: ComponentLink<Self>) -> Self {
+        Self {
+            props,
+            preview_ref: NodeRef::default(),
+            rendered: None,
+        }
+    }
+
+    fn update(&mut self, _: Self::Message) -> ShouldRender {
+        false
+    }
+
+    fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let visibility_changed = self.props.visible != props.visible;
+        self.props = props;
+        if !visibility_changed {
+            self.render_content();
+            self.scroll_to_line();
+        }
+        visibility_changed
+    }
+
+    fn view(&self) -> Html {
+        html! {
+            <div
+              ref=self.preview_ref.clone()
+              id="preview"
+              class=if self.props.visible { None } else { Some("hidden") }
+            />
+        }
+    }
+
+    fn rendered(&mut self, _first_render: bool) {
+        self.render_content();
+        self.scroll_to_line();
+    }
+}
+
+impl Preview {
+    fn render_content(&mut self) {
+        if !self.props.visible || self.rendered.as_ref() == Some(&self.props.content) {
+            return;
+        }
+        if let Some(element) = self.preview_ref.cast::<HtmlElement>() {
+            element.set_inner_html(&markdown::render(&self.props.content));
+            self.rendered = Some(self.props.content.clone());
+        }
+    }
+
+    fn scroll_to_line(&self) {
+        if !self.props.visible {
+            return;
+        }
+        let element = match self.preview_ref.cast::<HtmlElement>() {
+            Some(e) => e,
+            None => return,
+        };
+        // Line markers are emitted as direct children of the preview.
+        let mut top = 0;
+        let mut child = element.first_element_child();
+        while let Some(c) = child {
+            child = c.next_element_sibling();
+            let line = match c
+                .get_attribute("data-line")
+                .and_then(|l| l.parse::<u32>().ok())
+            {
+                Some(l) => l,
+                None => continue,
+            };
+            if line > self.props.line {
+                break;
+            }
+            if let Ok(marker) = c.dyn_into::<HtmlElement>() {
+                top = marker.offset_top();
+            }
+        }
+        element.set_scroll_top(top);
+    }
+}
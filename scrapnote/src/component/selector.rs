@@ -1,24 +1,38 @@
 use wasm_bindgen::prelude::*;
 
-use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use yew::events::KeyboardEvent;
-use yew::format::Json;
 use yew::prelude::*;
-use yew::services::fetch::{FetchService, FetchTask, Request, Response};
+use yew::services::fetch::FetchTask;
 
-use common::{GetFilesResponse, Item};
+use common::api::{self, Endpoint};
+use common::{CreateScratchResponse, GetFilesResponse, GetVaultsResponse, Item, ItemType};
+
+/// Start of the commands that switch to another vault, followed by its name.
+pub const SWITCH_VAULT: &str = "vault:";
 
 #[wasm_bindgen(module = "/js/list.js")]
 extern "C" {
     fn scroll_list();
 }
 
+#[wasm_bindgen(module = "/js/events.js")]
+extern "C" {
+    fn on_file_event(callback: &Closure<dyn FnMut(String)>);
+}
+
 pub struct Selector {
     link: ComponentLink<Self>,
     props: Props,
     input_ref: NodeRef,
     fetch_task: Option<FetchTask>,
+    scratch_task: Option<FetchTask>,
+    vaults_task: Option<FetchTask>,
+    /// Names of all vaults, offered as commands to switch to.
+    vaults: Vec<String>,
+    /// The last request that failed, shown until the next one succeeds.
+    error: Option<String>,
     state: State,
+    _on_file_event: Closure<dyn FnMut(String)>,
 }
 
 struct State {
@@ -48,8 +62,8 @@ impl State {
 
     fn on_file_updated(&mut self, files: Vec<Item>) {
         self.items = files;
-        if self.items.len() - 1 < self.list_index {
-            self.list_index = self.items.len() - 1;
+        if self.items.len().saturating_sub(1) < self.list_index {
+            self.list_index = self.items.len().saturating_sub(1);
         }
     }
 }
@@ -62,7 +76,13 @@ pub enum Msg {
     MoveSelectionUp,
     MoveSelectionDown,
     Enter,
+    Select(usize),
+    CreateScratch,
+    ScratchCreated(String),
     UpdateFiles(Vec<Item>),
+    FilesChanged,
+    UpdateVaults(Vec<String>),
+    RequestFailed(String),
 }
 
 #[derive(Clone, Debug, PartialEq, Properties)]
@@ -70,19 +90,36 @@ pub struct Props {
     pub on_file_select: Callback<String>,
     pub on_command_select: Callback<String>,
     pub focus: bool,
+    /// The active vault, whose notes are listed.
+    pub vault: String,
 }
 
 impl Component for Selector {
     type Message = Msg;
     type Properties = Props;
 
+    #[allow(unused_unsafe)]
     fn create(props: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let on_file_event_link = link.clone();
+        let on_file_event_callback =
+            Closure::wrap(
+                Box::new(move |_| on_file_event_link.send_message(Msg::FilesChanged))
+                    as Box<dyn FnMut(String)>,
+            );
+        unsafe {
+            on_file_event(&on_file_event_callback);
+        }
         Self {
             link,
             props,
             input_ref: NodeRef::default(),
             fetch_task: None,
+            scratch_task: None,
+            vaults_task: None,
+            vaults: Vec::new(),
+            error: None,
             state: State::new(),
+            _on_file_event: on_file_event_callback,
         }
     }
 
@@ -113,14 +150,44 @@ impl Component for Selector {
                 }
                 return true;
             }
+            Msg::Enter if self.state.input.is_empty() => {
+                self.create_scratch();
+            }
             Msg::Enter => {
                 match self.state.items.get(self.state.list_index) {
-                    Some(f) => self.props.on_file_select.emit(f.name.to_owned()),
+                    Some(i) => self.select(i),
                     None => self.props.on_file_select.emit(self.state.input.to_owned()),
                 };
             }
-            Msg::UpdateFiles(files) => {
+            Msg::Select(index) => {
+                if let Some(i) = self.state.items.get(index) {
+                    self.state.list_index = index;
+                    self.select(i);
+                    return true;
+                }
+            }
+            Msg::CreateScratch => {
+                self.create_scratch();
+            }
+            Msg::ScratchCreated(name) => {
+                self.props.on_file_select.emit(name);
+            }
+            Msg::UpdateFiles(mut files) => {
+                files.extend(self.vault_commands());
                 self.state.on_file_updated(files);
+                self.error = None;
+                return true;
+            }
+            Msg::FilesChanged => {
+                let input = self.state.input.to_owned();
+                self.query_files(&input);
+            }
+            Msg::UpdateVaults(vaults) => {
+                self.vaults = vaults;
+            }
+            Msg::RequestFailed(error) => {
+                log::error!("{}", error);
+                self.error = Some(error);
                 return true;
             }
         }
@@ -128,7 +195,12 @@ impl Component for Selector {
     }
 
     fn change(&mut self, props: Self::Properties) -> ShouldRender {
+        let vault_changed = props.vault != self.props.vault;
         self.props = props;
+        if vault_changed {
+            let input = self.state.input.to_owned();
+            self.query_files(&input);
+        }
         if self.props.focus {
             self.focus_input();
         }
@@ -139,6 +211,7 @@ impl Component for Selector {
         html! {
             <div id="command-wrapper">
             { self.view_input() }
+            { self.view_error() }
             <div id="list">
               { for self.state.items.iter().enumerate().map(|(idx, i)| self.view_list_item(idx, i)) }
             </div>
@@ -151,6 +224,7 @@ impl Component for Selector {
         if first_render {
             self.focus_input();
             self.query_files("");
+            self.query_vaults();
         }
         unsafe {
             scroll_list();
@@ -182,6 +256,7 @@ impl Selector {
                         (true, "e") => Msg::MoveCaretToEnd,
                         (true, "p") => Msg::MoveSelectionUp,
                         (true, "n") => Msg::MoveSelectionDown,
+                        (true, "Enter") => Msg::CreateScratch,
                         (_, "Enter") => Msg::Enter,
                         (_, _) => Msg::None(e.is_composing())
                     }
@@ -190,10 +265,26 @@ impl Selector {
         }
     }
 
+    fn view_error(&self) -> Html {
+        match &self.error {
+            Some(e) => html! { <div class="notice">{ e }</div> },
+            None => html! {},
+        }
+    }
+
     fn view_list_item(&self, index: usize, item: &Item) -> Html {
-        let is_selected = self.state.list_index == index;
+        let mut classes = Vec::new();
+        if self.state.list_index == index {
+            classes.push("selected");
+        }
+        if let ItemType::Command = item.item_type {
+            classes.push("command");
+        }
         html! {
-            <div class=if is_selected { Some("selected") } else { None }>
+            <div
+              class=classes
+              onclick=self.link.callback(move |_| Msg::Select(index))
+            >
               <div>{ &item.name }</div>
             </div>
         }
@@ -222,29 +313,66 @@ impl Selector {
     }
 
     fn query_files(&mut self, input: &str) {
-        let port = yew::utils::window().location().port().unwrap();
-        let query = if input.is_empty() {
-            "".to_string()
-        } else {
-            format!(
-                "?key={}",
-                utf8_percent_encode(input, NON_ALPHANUMERIC).to_string()
-            )
+        let endpoint = api::ListFiles {
+            key: input.to_string(),
         };
-        log::info!("http://127.0.0.1:{}/api/files{}", port, query);
-        let request = Request::get(format!("http://127.0.0.1:{}/api/files{}", port, query))
-            .body(yew::format::Nothing)
-            .expect("build request to query files");
-        log::info!("request: {:?}", request);
+        log::info!("{}", endpoint.path());
+        let callback =
+            self.link.callback(
+                |result: Result<GetFilesResponse, anyhow::Error>| match result {
+                    Ok(response) => Msg::UpdateFiles(response.files),
+                    Err(e) => Msg::RequestFailed(format!("failed to list notes: {}", e)),
+                },
+            );
+        drop(self.fetch_task.take());
+        self.fetch_task = Some(crate::api::fetch(endpoint, callback));
+    }
+
+    fn query_vaults(&mut self) {
+        let callback =
+            self.link.callback(
+                |result: Result<GetVaultsResponse, anyhow::Error>| match result {
+                    Ok(response) => Msg::UpdateVaults(response.vaults),
+                    Err(e) => Msg::RequestFailed(format!("failed to list vaults: {}", e)),
+                },
+            );
+        self.vaults_task = Some(crate::api::fetch(api::ListVaults, callback));
+    }
+
+    /// Commands switching to the vaults other than the active one that match
+    /// the input. None are offered until something is typed.
+    fn vault_commands(&self) -> Vec<Item> {
+        if self.state.input.is_empty() {
+            return Vec::new();
+        }
+        self.vaults
+            .iter()
+            .filter(|v| **v != self.props.vault)
+            .map(|v| format!("{}{}", SWITCH_VAULT, v))
+            .filter(|c| c.contains(&self.state.input))
+            .map(|name| Item {
+                name,
+                item_type: ItemType::Command,
+            })
+            .collect()
+    }
 
+    fn select(&self, item: &Item) {
+        match item.item_type {
+            ItemType::File => self.props.on_file_select.emit(item.name.to_owned()),
+            ItemType::Command => self.props.on_command_select.emit(item.name.to_owned()),
+        }
+    }
+
+    fn create_scratch(&mut self) {
         let callback = self.link.callback(
-            |response: Response<Json<Result<GetFilesResponse, anyhow::Error>>>| {
-                let Json(data) = response.into_body();
-                Msg::UpdateFiles(data.unwrap().files)
+            |result: Result<CreateScratchResponse, anyhow::Error>| match result {
+                Ok(response) => Msg::ScratchCreated(response.name),
+                Err(e) => Msg::RequestFailed(format!("failed to create a scratch note: {}", e)),
             },
         );
-        drop(self.fetch_task.take());
-        self.fetch_task = Some(FetchService::fetch(request, callback).expect("fetch files"));
+        drop(self.scratch_task.take());
+        self.scratch_task = Some(crate::api::fetch(api::CreateScratch, callback));
     }
 }
 
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use yew::format::Json;
+use yew::services::storage::{Area, StorageService};
+
+const KEY_PREFIX: &str = "scrapnote.draft.";
+/// Prefix of the drafts of notes in vaults other than the default one.
+const VAULT_KEY_PREFIX: &str = "scrapnote.vault-draft.";
+
+/// Unsaved content of a note, kept in local storage so that it survives the
+/// webview being closed or killed before the next save.
+#[derive(Deserialize, Serialize)]
+pub struct Draft {
+    pub content: String,
+    /// Time the draft was written in milliseconds since the epoch.
+    pub saved_at: f64,
+}
+
+fn storage() -> Option<StorageService> {
+    StorageService::new(Area::Local).ok()
+}
+
+/// Key of the draft of `file_name` in the active vault.
+fn key(file_name: &str) -> String {
+    let vault = crate::api::vault();
+    if vault == common::DEFAULT_VAULT {
+        format!("{}{}", KEY_PREFIX, file_name)
+    } else {
+        format!(
+            "{}{}/{}",
+            VAULT_KEY_PREFIX,
+            common::path::encode_component(&vault),
+            file_name
+        )
+    }
+}
+
+pub fn store(file_name: &str, content: &str) {
+    if let Some(mut storage) = storage() {
+        let draft = Draft {
+            content: content.to_string(),
+            saved_at: js_sys::Date::now(),
+        };
+        storage.store(&key(file_name), Json(&draft));
+    }
+}
+
+pub fn load(file_name: &str) -> Option<Draft> {
+    let storage = storage()?;
+    let Json(draft): Json<Result<Draft, anyhow::Error>> = storage.restore(&key(file_name));
+    draft.ok()
+}
+
+pub fn clear(file_name: &str) {
+    if let Some(mut storage) = storage() {
+        storage.remove(&key(file_name));
+    }
+}
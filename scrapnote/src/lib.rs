@@ -1,32 +1,69 @@
 #![recursion_limit = "1024"]
+mod api;
 mod component;
+mod draft;
 use component::editor::Editor;
-use component::selector::Selector;
+use component::selector::{Selector, SWITCH_VAULT};
 
 use wasm_bindgen::prelude::*;
 use yew::prelude::*;
 
+use common::AppCommand;
+
+#[wasm_bindgen(module = "/js/events.js")]
+extern "C" {
+    fn on_command(callback: &Closure<dyn FnMut(String)>);
+}
+
+#[wasm_bindgen(module = "/js/ipc.js")]
+extern "C" {
+    fn install_fetch_shim();
+}
+
+#[wasm_bindgen(module = "/js/window.js")]
+extern "C" {
+    fn install_resize_listener();
+}
+
 struct ScrapNote {
     link: ComponentLink<Self>,
     focus: Focus,
     editing: Option<String>,
+    vault: String,
+    /// Vault switched to once the note being edited has been closed.
+    switching_vault: Option<String>,
+    _on_command: Closure<dyn FnMut(String)>,
 }
 
 enum Msg {
     FileSelect(String),
+    FileSaved(Option<String>),
     CommandSelect(String),
+    VaultSwitched(String),
     FocusCommand,
+    AppCommand(String),
 }
 
 impl Component for ScrapNote {
     type Message = Msg;
     type Properties = ();
 
+    #[allow(unused_unsafe)]
     fn create(_: Self::Properties, link: ComponentLink<Self>) -> Self {
+        let on_command_link = link.clone();
+        let on_command_callback = Closure::wrap(Box::new(move |command| {
+            on_command_link.send_message(Msg::AppCommand(command))
+        }) as Box<dyn FnMut(String)>);
+        unsafe {
+            on_command(&on_command_callback);
+        }
         Self {
             link,
             focus: Focus::Command,
             editing: None,
+            vault: crate::api::vault(),
+            switching_vault: None,
+            _on_command: on_command_callback,
         }
     }
 
@@ -37,10 +74,31 @@ impl Component for ScrapNote {
                 self.editing = Some(f);
                 self.focus = Focus::Editor;
             }
-            Msg::CommandSelect(_) => {}
+            Msg::FileSaved(f) => {
+                self.editing = f;
+            }
+            Msg::CommandSelect(c) => match c.strip_prefix(SWITCH_VAULT) {
+                Some(vault) => {
+                    // The editor saves the edits left in the note as it is
+                    // closed, which has to happen in the vault it belongs to.
+                    self.switching_vault = Some(vault.to_string());
+                    self.editing = None;
+                    self.focus = Focus::Command;
+                }
+                None => log::error!("unknown command: {}", c),
+            },
+            Msg::VaultSwitched(vault) => {
+                log::info!("switched to vault: {}", vault);
+                api::set_vault(&vault);
+                self.vault = vault;
+            }
             Msg::FocusCommand => {
                 self.focus = Focus::Command;
             }
+            Msg::AppCommand(command) => match serde_json::from_str(&command) {
+                Ok(AppCommand::Open(f)) => self.link.send_message(Msg::FileSelect(f)),
+                Err(e) => log::error!("invalid command {}: {}", command, e),
+            },
         }
         true
     }
@@ -49,6 +107,12 @@ impl Component for ScrapNote {
         false
     }
 
+    fn rendered(&mut self, _first_render: bool) {
+        if let Some(vault) = self.switching_vault.take() {
+            self.link.send_message(Msg::VaultSwitched(vault));
+        }
+    }
+
     fn view(&self) -> Html {
         html! {
             <div id="container">
@@ -56,6 +120,7 @@ impl Component for ScrapNote {
                   on_file_select=self.link.callback(|f| Msg::FileSelect(f))
                   on_command_select=self.link.callback(|c| Msg::CommandSelect(c))
                   focus=self.focus == Focus::Command
+                  vault=&self.vault
                 />
                 { self.view_editor() }
             </div>
@@ -66,18 +131,21 @@ impl Component for ScrapNote {
 impl ScrapNote {
     fn view_editor(&self) -> Html {
         let on_unfocus = self.link.callback(|_| Msg::FocusCommand);
+        let on_save = self.link.callback(Msg::FileSaved);
         match &self.editing {
             Some(s) => html! {
                 <Editor
                   file_name=s
                   focus=self.focus == Focus::Editor
                   on_unfocus=on_unfocus
+                  on_save=on_save
                 />
             },
             None => html! {
                 <Editor
                   focus=self.focus == Focus::Editor
                   on_unfocus=on_unfocus
+                  on_save=on_save
                 />
             },
         }
@@ -90,8 +158,17 @@ enum Focus {
     Editor,
 }
 
-#[wasm_bindgen(start)]
+/// Starts the app. Called by `main.js` once the module is loaded, so that the
+/// app is mounted only once.
+#[wasm_bindgen]
+#[allow(unused_unsafe)]
 pub fn run_app() {
     wasm_logger::init(wasm_logger::Config::default());
+    // Requests must go through the native bridge before the first one is
+    // made.
+    unsafe {
+        install_fetch_shim();
+        install_resize_listener();
+    }
     App::<ScrapNote>::new().mount_to_body();
 }
@@ -1,95 +1,799 @@
 use bytes::buf::ext::BufExt;
+use hyper::body::HttpBody;
+use hyper::header::{
+    ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE,
+    CONTENT_SECURITY_POLICY, CONTENT_TYPE, RANGE, X_CONTENT_TYPE_OPTIONS,
+};
 use hyper::{Body, Error, Method, Request, Response};
-use std::io::Write;
+use serde::Deserialize;
+use tokio::sync::broadcast::RecvError;
 
-use crate::build_404_response;
-use crate::config::Config;
-use common::{GetFileContentResponse, GetFilesResponse, Item, ItemType, SaveFileContentRequest};
+use crate::router::{self, Match, Router};
+use crate::store::NoteStore;
+use crate::{
+    attachment, build_404_response, build_status_response, capture, export, external, scratch,
+    AppState, Vault,
+};
+use common::{
+    api, path, CaptureRequest, CaptureResponse, CreateScratchResponse, DeleteFileResponse,
+    ExportRequest, ExportResponse, GetFileContentResponse, GetVaultsResponse, Item, ItemType,
+    OpenExternalResponse, RenameFileRequest, RenameFileResponse, SaveFileContentRequest,
+    SaveFileContentResponse, UploadAttachmentResponse, DEFAULT_VAULT,
+};
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+enum Endpoint {
+    ListFiles,
+    GetFile,
+    SaveFile,
+    DeleteFile,
+    RenameFile,
+    OpenExternal,
+    CreateScratch,
+    Capture,
+    Export,
+    Events,
+    UploadAttachment,
+    GetAttachment,
+    ListVaults,
+}
+
+impl Endpoint {
+    /// Whether the endpoint works on the notes of a vault, so that it is
+    /// also served below a vault segment.
+    fn in_vault(self) -> bool {
+        !matches!(self, Endpoint::Events | Endpoint::ListVaults)
+    }
+}
+
+fn router() -> Router<Endpoint> {
+    Router::new()
+        .endpoint::<api::ListFiles>(Endpoint::ListFiles)
+        .endpoint::<api::GetFile>(Endpoint::GetFile)
+        .endpoint::<api::SaveFile>(Endpoint::SaveFile)
+        .endpoint::<api::DeleteFile>(Endpoint::DeleteFile)
+        .endpoint::<api::RenameFile>(Endpoint::RenameFile)
+        .endpoint::<api::OpenExternal>(Endpoint::OpenExternal)
+        .endpoint::<api::CreateScratch>(Endpoint::CreateScratch)
+        .endpoint::<api::Capture>(Endpoint::Capture)
+        .endpoint::<api::Export>(Endpoint::Export)
+        .route(Method::GET, api::EVENTS_PATH, Endpoint::Events)
+        .endpoint::<api::UploadAttachment>(Endpoint::UploadAttachment)
+        .route(
+            Method::GET,
+            &format!("{}{{id}}", api::ATTACHMENT_PREFIX),
+            Endpoint::GetAttachment,
+        )
+        .endpoint::<api::ListVaults>(Endpoint::ListVaults)
+}
+
+/// Splits the vault segment off `path`, returning the vault, if any, and the
+/// path of the route. Returns `None` if the segment doesn't decode.
+fn split_vault(path: &str) -> Option<(Option<String>, String)> {
+    let rest = match path.strip_prefix(api::VAULT_PREFIX) {
+        Some(r) => r,
+        None => return Some((None, path.to_string())),
+    };
+    let (vault, rest) = rest.split_once('/')?;
+    let vault = path::decode_component(vault).filter(|v| !v.is_empty())?;
+    Some((Some(vault), format!("/api/{}", rest)))
+}
+
+#[derive(Deserialize)]
+struct ListFilesQuery {
+    #[serde(default)]
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct GetFileQuery {
+    /// Whether a missing note reads as empty instead of not found. Defaults
+    /// to `true`.
+    create: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct UploadAttachmentQuery {
+    /// Name of the uploaded file, used for the extension of the attachment.
+    name: Option<String>,
+}
+
+/// Whether `req` may only be made from this machine.
+pub fn is_local_only(req: &Request<Body>) -> bool {
+    let path = match split_vault(req.uri().path()) {
+        Some((_, p)) => p,
+        None => return false,
+    };
+    matches!(
+        router().find(req.method(), &path),
+        Match::Found(Endpoint::OpenExternal, _)
+    )
+}
+
 pub async fn handle_api_request(
     req: Request<Body>,
-    config: Arc<Config>,
+    state: Arc<AppState>,
 ) -> Result<Response<Body>, Error> {
-    let path = req.uri().path();
-    let directory = &config.file_directory;
-    return if path.starts_with("/api/files") {
-        handle_files(req, directory)
-    } else if path.starts_with("/api/file/") {
-        handle_file(req, directory).await
-    } else {
-        Ok(build_404_response())
+    let (vault_name, path) = match split_vault(req.uri().path()) {
+        Some(v) => v,
+        None => return Ok(build_404_response()),
     };
+    let (endpoint, params) = match router().find(req.method(), &path) {
+        Match::Found(e, p) => (e, p),
+        Match::MethodNotAllowed(allowed) => return Ok(router::method_not_allowed(&allowed)),
+        Match::NotFound => return Ok(build_404_response()),
+    };
+    if vault_name.is_some() && !endpoint.in_vault() {
+        return Ok(build_404_response());
+    }
+    let vault = match state.vault(vault_name.as_deref().unwrap_or(DEFAULT_VAULT)) {
+        Some(v) => v.clone(),
+        None => return Ok(build_404_response()),
+    };
+    let param = |name| params.get(name).unwrap_or_default().to_string();
+    match endpoint {
+        Endpoint::ListFiles => match router::query(&req) {
+            Ok(query) => handle_files(query, &vault, &state),
+            Err(_) => Ok(build_status_response(400)),
+        },
+        Endpoint::GetFile => match router::query::<GetFileQuery>(&req) {
+            Ok(query) => {
+                let create = query.create.unwrap_or(true);
+                handle_get_file(param("name"), create, &vault, &state).await
+            }
+            Err(_) => Ok(build_status_response(400)),
+        },
+        Endpoint::SaveFile => handle_save_file(req, param("name"), &vault, &state).await,
+        Endpoint::DeleteFile => handle_delete_file(param("name"), &vault, &state).await,
+        Endpoint::RenameFile => handle_rename_file(req, &vault, &state).await,
+        Endpoint::OpenExternal => handle_open_external(param("name"), &vault, &state).await,
+        Endpoint::CreateScratch => handle_scratch(&vault, &state).await,
+        Endpoint::Capture => handle_capture(req, &vault, &state).await,
+        Endpoint::Export => handle_export(req, &vault, &state).await,
+        Endpoint::Events => handle_events(&state),
+        Endpoint::UploadAttachment => match router::query(&req) {
+            Ok(query) => handle_upload_attachment(req, query, &vault, &state).await,
+            Err(_) => Ok(build_status_response(400)),
+        },
+        Endpoint::GetAttachment => handle_get_attachment(req, &param("id"), &vault, &state).await,
+        Endpoint::ListVaults => handle_vaults(&state),
+    }
 }
 
-fn handle_files(req: Request<Body>, directory: &str) -> Result<Response<Body>, Error> {
-    if req.method() != &Method::GET {
-        return Ok(build_404_response());
+/// Names read ahead of the client when listing notes.
+const LIST_BUFFER: usize = 256;
+
+/// Lists the notes whose names contain `key`. The list is streamed as the
+/// directories are read, so that huge folders don't hold up the response.
+fn handle_files(
+    query: ListFilesQuery,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    println!("requested: {}", query.key);
+    let key = query.key;
+    // Bounded, so that listing pauses while the client is slow to read.
+    let (names, received) = std::sync::mpsc::sync_channel(LIST_BUFFER);
+    let list_state = state.clone();
+    let store = vault.store.clone();
+    tokio::task::spawn_blocking(move || {
+        for directory in &["", list_state.config.inbox_directory.as_str()] {
+            let result = store.visit(directory, &mut |entry| {
+                if entry.is_directory || !entry.name.contains(&key) {
+                    return true;
+                }
+                // Fails once the client went away.
+                names.send(entry.name).is_ok()
+            });
+            if let Err(e) = result {
+                println!("failed to list {}: {}", directory, e);
+            }
+        }
+    });
+
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut chunk = String::from("{\"files\":[");
+        let mut first = true;
+        let mut received = received;
+        loop {
+            // Waits for the next name along with whatever was read in the
+            // meantime, which is sent as one chunk.
+            let (rest, batch) = blocking(move || {
+                let batch: Vec<String> = received
+                    .recv()
+                    .into_iter()
+                    .chain(received.try_iter().take(LIST_BUFFER))
+                    .collect();
+                (received, batch)
+            })
+            .await;
+            if batch.is_empty() {
+                break;
+            }
+            received = rest;
+            for name in batch {
+                if !first {
+                    chunk.push(',');
+                }
+                first = false;
+                let item = Item {
+                    name,
+                    item_type: ItemType::File,
+                };
+                chunk.push_str(&serde_json::to_string(&item).expect("serialize Item"));
+            }
+            let sent = std::mem::take(&mut chunk);
+            if sender.send_data(sent.into()).await.is_err() {
+                return;
+            }
+        }
+        chunk.push_str("]}");
+        let _ = sender.send_data(chunk.into()).await;
+    });
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(body)
+        .unwrap())
+}
+
+/// Runs blocking note I/O off the runtime's worker threads.
+async fn blocking<T, F>(f: F) -> T
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .expect("run blocking task")
+}
+
+async fn handle_scratch(vault: &Vault, state: &Arc<AppState>) -> Result<Response<Body>, Error> {
+    let state = state.clone();
+    let store = vault.store.clone();
+    let name = match blocking(move || scratch::create(store.as_ref(), &state.config)).await {
+        Ok(n) => n,
+        Err(e) => return Ok(io_error_response(&e)),
+    };
+    println!("created scratch note: {}", name);
+    let body = serde_json::to_string(&CreateScratchResponse { name })
+        .expect("create CreateScratchResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn handle_capture(
+    req: Request<Body>,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let body = hyper::body::aggregate(req).await?;
+    let value: CaptureRequest = match serde_json::from_reader(body.reader()) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("invalid capture request: {}", e);
+            return Ok(Response::builder().status(400).body(Body::empty()).unwrap());
+        }
+    };
+    let _guard = state.lock_writes().await;
+    let note = value.note.as_deref().unwrap_or(&state.config.capture_note);
+    if external::is_open(state, vault, note) {
+        return Ok(build_status_response(409));
     }
-    let file = match req.uri().query() {
-        Some(q) => {
-            let key_idx = q.find("key=").unwrap();
-            &q[key_idx + 4..]
-        }
-        None => "",
-    };
-
-    println!("requested: {}", file);
-    let files = std::fs::read_dir(directory)
-        .unwrap()
-        .filter_map(|res| res.map(|e| e.file_name().into_string()).ok())
-        .filter_map(|r| r.ok())
-        .filter(|f| f.contains(file))
-        .map(|f| Item {
-            name: f,
-            item_type: ItemType::File,
-        })
-        .collect::<Vec<_>>();
-
-    let body = serde_json::to_string(&GetFilesResponse { files }).unwrap();
+    let capture_state = state.clone();
+    let store = vault.store.clone();
+    let name = blocking(move || {
+        capture::append(
+            store.as_ref(),
+            &capture_state.config,
+            value.note.as_deref(),
+            value.heading.as_deref(),
+            &value.text,
+        )
+    })
+    .await;
+    let name = match name {
+        Ok(n) => n,
+        Err(e) => return Ok(io_error_response(&e)),
+    };
+    println!("captured to: {}", name);
+    let body =
+        serde_json::to_string(&CaptureResponse { name }).expect("create CaptureResponse");
     Ok(Response::builder()
         .status(200)
+        .header(CONTENT_TYPE, "application/json")
         .body(Body::from(body))
         .unwrap())
 }
 
-async fn handle_file(req: Request<Body>, directory: &str) -> Result<Response<Body>, Error> {
-    let file_name = &req.uri().path()[10..];
-    let path = format!("{}/{}", directory, file_name);
-    match req.method() {
-        &Method::GET => {
-            println!("requested file content: {}", path);
-            let content = std::fs::read_to_string(&path);
-            let body = match content {
-                Ok(c) => serde_json::to_string(&GetFileContentResponse { content: c })
-                    .expect("create GetFileContentResponse"),
-                Err(_) => {
-                    println!("file not found: {}", &path);
-                    std::fs::File::create(path).expect("create empty file");
-                    serde_json::to_string(&GetFileContentResponse {
-                        content: "".to_string(),
-                    })
-                    .expect("create GetFileContentResponse")
+async fn handle_export(
+    req: Request<Body>,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let body = hyper::body::aggregate(req).await?;
+    let value: ExportRequest = match serde_json::from_reader(body.reader()) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("invalid export request: {}", e);
+            return Ok(Response::builder().status(400).body(Body::empty()).unwrap());
+        }
+    };
+    let out_directory = match &state.config.export_directory {
+        Some(d) if vault.name == DEFAULT_VAULT => PathBuf::from(d),
+        Some(d) => Path::new(d).join(&vault.name),
+        None => {
+            println!("export refused: no export directory configured");
+            return Ok(build_status_response(403));
+        }
+    };
+    let options = export::ExportOptions {
+        out_directory,
+        tag: value.tag,
+        folder: value.folder,
+        vault: vault.name.clone(),
+    };
+    let state = state.clone();
+    let store = vault.store.clone();
+    let pages = blocking(move || export::export_html(store.as_ref(), &state.config, &options));
+    let pages = match pages.await {
+        Ok(p) => p,
+        Err(e) => {
+            println!("export failed: {}", e);
+            return Ok(Response::builder().status(500).body(Body::empty()).unwrap());
+        }
+    };
+    let body = serde_json::to_string(&ExportResponse { pages }).expect("create ExportResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+/// Streams file changes in all vaults as Server-Sent Events.
+fn handle_events(state: &AppState) -> Result<Response<Body>, Error> {
+    let mut events = state.events.subscribe();
+    let shutdown = crate::wait_for(state.shutdown.clone());
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        // The stream never ends on its own, which would keep a graceful
+        // shutdown waiting.
+        tokio::pin!(shutdown);
+        loop {
+            let received = tokio::select! {
+                received = events.recv() => received,
+                _ = &mut shutdown => break,
+            };
+            let event = match received {
+                Ok(e) => e,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let data = format!(
+                "data: {}\n\n",
+                serde_json::to_string(&event).expect("serialize FileEvent")
+            );
+            if sender.send_data(data.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(body)
+        .unwrap())
+}
+
+async fn handle_upload_attachment(
+    req: Request<Body>,
+    query: UploadAttachmentQuery,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let name = query.name;
+    let content_type = req
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string());
+    let data = match read_body(req, attachment::MAX_SIZE).await? {
+        Some(d) => d,
+        None => return Ok(build_status_response(413)),
+    };
+    let state = state.clone();
+    let store = vault.store.clone();
+    let id = blocking(move || {
+        attachment::store(
+            store.as_ref(),
+            &state.config,
+            name.as_deref(),
+            content_type.as_deref(),
+            &data,
+        )
+    })
+    .await;
+    let id = match id {
+        Ok(id) => id,
+        Err(e) => return Ok(io_error_response(&e)),
+    };
+    println!("stored attachment: {}", id);
+    let url = attachment::url(&vault.name, &id);
+    let body = serde_json::to_string(&UploadAttachmentResponse { id, url })
+        .expect("create UploadAttachmentResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn handle_get_attachment(
+    req: Request<Body>,
+    id: &str,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let name = match attachment::name(&state.config, id) {
+        Some(n) => n,
+        None => return Ok(build_404_response()),
+    };
+    let store = vault.store.clone();
+    let stat = {
+        let (store, name) = (store.clone(), name.clone());
+        blocking(move || store.stat(&name))
+    };
+    let length = match stat.await {
+        Ok(m) => m.len,
+        Err(_) => return Ok(build_404_response()),
+    };
+    let range = req
+        .headers()
+        .get(RANGE)
+        .and_then(|v| v.to_str().ok())
+        .map(|r| attachment::parse_range(r, length))
+        .unwrap_or(Ok(None));
+    let content_type = attachment::content_type(id);
+    let mut builder = Response::builder()
+        .header(X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(ACCEPT_RANGES, "bytes");
+    // Uploads are served from the origin of the app, so anything that could
+    // run script there is downloaded instead and sandboxed.
+    if !attachment::is_inline(&content_type) {
+        builder = builder
+            .header(CONTENT_DISPOSITION, "attachment")
+            .header(CONTENT_SECURITY_POLICY, "sandbox");
+    }
+    let builder = builder.header(CONTENT_TYPE, content_type);
+    let response = match range {
+        Ok(Some((start, end))) => builder
+            .status(206)
+            .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, length))
+            .body(stream_range(store, name, start, end + 1 - start)),
+        Ok(None) => builder
+            .status(200)
+            .body(stream_range(store, name, 0, length)),
+        Err(()) => builder
+            .status(416)
+            .header(CONTENT_RANGE, format!("bytes */{}", length))
+            .body(Body::empty()),
+    };
+    Ok(response.unwrap())
+}
+
+/// Collects the body of `req`, or `None` once it grows past `limit` bytes.
+async fn read_body(req: Request<Body>, limit: usize) -> Result<Option<Vec<u8>>, Error> {
+    let declared = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+    if matches!(declared, Some(l) if l > limit as u64) {
+        return Ok(None);
+    }
+    let mut body = req.into_body();
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if data.len() + chunk.len() > limit {
+            return Ok(None);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(Some(data))
+}
+
+/// Body with `len` bytes of `name` from `start` on, read in chunks as the
+/// client takes them.
+fn stream_range(store: Arc<dyn NoteStore>, name: String, start: u64, len: u64) -> Body {
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        let mut offset = start;
+        let end = start + len;
+        while offset < end {
+            let chunk_len = ((end - offset) as usize).min(attachment::CHUNK_SIZE);
+            let store = store.clone();
+            let name = name.clone();
+            let chunk = blocking(move || store.read_range(&name, offset, chunk_len)).await;
+            let chunk = match chunk {
+                Ok(c) if !c.is_empty() => c,
+                _ => {
+                    // The file changed underneath; cut the response short so
+                    // the client doesn't take it for complete.
+                    sender.abort();
+                    break;
                 }
             };
-            Ok(Response::builder()
-                .status(200)
-                .body(Body::from(body))
-                .unwrap())
+            offset += chunk.len() as u64;
+            if sender.send_data(chunk.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+    body
+}
+
+async fn handle_get_file(
+    file_name: String,
+    create: bool,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let store = vault.store.clone();
+    let editing_externally = external::is_open(state, vault, &file_name);
+    println!("requested file content: {}", file_name);
+    let response = blocking(move || match store.read_to_string(&file_name) {
+        Ok(c) => Some(GetFileContentResponse {
+            content: c,
+            modified: modified_millis(store.as_ref(), &file_name),
+            editing_externally,
+        }),
+        Err(_) if !create => None,
+        // The note is created when it is first saved.
+        Err(_) => {
+            println!("file not found: {}", &file_name);
+            Some(GetFileContentResponse {
+                content: "".to_string(),
+                modified: None,
+                editing_externally,
+            })
+        }
+    })
+    .await;
+    let response = match response {
+        Some(r) => r,
+        None => return Ok(build_404_response()),
+    };
+    let body = serde_json::to_string(&response).expect("create GetFileContentResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn handle_save_file(
+    req: Request<Body>,
+    file_name: String,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let body = hyper::body::aggregate(req).await?;
+    let value: SaveFileContentRequest = match serde_json::from_reader(body.reader()) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("invalid save request: {}", e);
+            return Ok(build_status_response(400));
+        }
+    };
+    let _guard = state.lock_writes().await;
+    // The external editor would overwrite the save with its copy.
+    if external::is_open(state, vault, &file_name) {
+        return Ok(build_status_response(409));
+    }
+    let save_state = state.clone();
+    let save_store = vault.store.clone();
+    let name = blocking(move || {
+        let store = save_store.as_ref();
+        if let Some(expected) = value.expected_modified {
+            if modified_millis(store, &file_name) != Some(expected) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::AlreadyExists,
+                    format!("{} was changed since it was read", file_name),
+                ));
+            }
+        }
+        if value.create_new {
+            store.create(&file_name)?;
+        }
+        store.write(&file_name, value.content.as_bytes())?;
+        let name = if value.autosave {
+            Some(file_name)
+        } else {
+            // The note is saved by now, so a failed tidy-up only leaves it
+            // under its scratch name.
+            match scratch::on_saved(store, &save_state.config, &file_name, &value.content) {
+                Ok(None) => {
+                    println!("removed empty scratch note: {}", file_name);
+                    None
+                }
+                Ok(Some(n)) => {
+                    if n != file_name {
+                        println!("renamed scratch note: {} -> {}", file_name, n);
+                    }
+                    Some(n)
+                }
+                Err(e) => {
+                    println!("failed to tidy up scratch note {}: {}", file_name, e);
+                    Some(file_name)
+                }
+            }
+        };
+        let modified = name.as_ref().and_then(|n| modified_millis(store, n));
+        Ok((name, modified))
+    })
+    .await;
+    let (name, modified) = match name {
+        Ok(n) => n,
+        Err(e) => return Ok(io_error_response(&e)),
+    };
+    let body = serde_json::to_string(&SaveFileContentResponse { name, modified })
+        .expect("create SaveFileContentResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn handle_delete_file(
+    file_name: String,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let _guard = state.lock_writes().await;
+    let store = vault.store.clone();
+    println!("delete: {}", file_name);
+    if let Err(e) = blocking(move || store.delete(&file_name)).await {
+        return Ok(io_error_response(&e));
+    }
+    let body = serde_json::to_string(&DeleteFileResponse {}).expect("create DeleteFileResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+async fn handle_rename_file(
+    req: Request<Body>,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    let body = hyper::body::aggregate(req).await?;
+    let value: RenameFileRequest = match serde_json::from_reader(body.reader()) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("invalid rename request: {}", e);
+            return Ok(build_status_response(400));
+        }
+    };
+    let _guard = state.lock_writes().await;
+    let store = vault.store.clone();
+    println!("rename: {} -> {}", value.from, value.to);
+    let result = blocking(move || {
+        if store.exists(&value.to) {
+            return Err(std::io::Error::from(std::io::ErrorKind::AlreadyExists));
         }
+        store.rename(&value.from, &value.to).map(|_| value.to)
+    });
+    let name = match result.await {
+        Ok(n) => n,
+        Err(e) => return Ok(io_error_response(&e)),
+    };
+    let body =
+        serde_json::to_string(&RenameFileResponse { name }).expect("create RenameFileResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
 
-        &Method::POST => {
-            let body = hyper::body::aggregate(req).await?;
-            let value: SaveFileContentRequest =
-                serde_json::from_reader(body.reader()).expect("parse request body");
-            println!("write: {}", &value.content);
-            std::fs::File::create(&path)
-                .expect(&format!("open file {}", &path))
-                .write_all(value.content.as_bytes())
-                .expect("write contents");
-            Ok(Response::builder().status(200).body(Body::empty()).unwrap())
+async fn handle_open_external(
+    file_name: String,
+    vault: &Vault,
+    state: &Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    if let Err(e) = external::open(state, vault, &file_name).await {
+        return Ok(io_error_response(&e));
+    }
+    let body =
+        serde_json::to_string(&OpenExternalResponse {}).expect("create OpenExternalResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+fn handle_vaults(state: &AppState) -> Result<Response<Body>, Error> {
+    let vaults = state.vaults.iter().map(|v| v.name.clone()).collect();
+    let body =
+        serde_json::to_string(&GetVaultsResponse { vaults }).expect("create GetVaultsResponse");
+    Ok(Response::builder()
+        .status(200)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body))
+        .unwrap())
+}
+
+fn io_error_response(e: &std::io::Error) -> Response<Body> {
+    let status = match e.kind() {
+        std::io::ErrorKind::NotFound => 404,
+        std::io::ErrorKind::AlreadyExists => 409,
+        std::io::ErrorKind::InvalidInput => 400,
+        _ => {
+            println!("note store error: {}", e);
+            500
         }
+    };
+    build_status_response(status)
+}
+
+fn modified_millis(store: &dyn NoteStore, name: &str) -> Option<u64> {
+    store.stat(name).ok()?.modified_millis()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(method: Method, path: &str) -> Option<(Option<String>, Endpoint, String)> {
+        let (vault, path) = split_vault(path)?;
+        match router().find(&method, &path) {
+            Match::Found(endpoint, params) => Some((
+                vault,
+                endpoint,
+                params.get("name").unwrap_or_default().to_string(),
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn vault_prefixed_paths_route_like_plain_ones() {
+        let (vault, endpoint, _) = route(Method::GET, "/api/vault/work/files").unwrap();
+        assert_eq!(vault.as_deref(), Some("work"));
+        assert!(matches!(endpoint, Endpoint::ListFiles));
+
+        let (vault, endpoint, name) =
+            route(Method::POST, "/api/vault/my%20notes/file/inbox/a%20b.md").unwrap();
+        assert_eq!(vault.as_deref(), Some("my notes"));
+        assert!(matches!(endpoint, Endpoint::SaveFile));
+        assert_eq!(name, "inbox/a b.md");
+
+        let (vault, endpoint, name) = route(Method::GET, "/api/file/todo.md").unwrap();
+        assert_eq!(vault, None);
+        assert!(matches!(endpoint, Endpoint::GetFile));
+        assert_eq!(name, "todo.md");
+    }
 
-        _ => Ok(build_404_response()),
+    #[test]
+    fn malformed_vault_segments_are_not_routed() {
+        assert!(split_vault("/api/vault/work").is_none());
+        assert!(split_vault("/api/vault//files").is_none());
+        assert!(split_vault("/api/vault/%FF/files").is_none());
+        assert!(route(Method::GET, "/api/vault/work/").is_none());
+        assert!(route(Method::GET, "/api/vault/work/filesXYZ").is_none());
     }
 }
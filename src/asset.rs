@@ -0,0 +1,162 @@
+use sha2::{Digest, Sha256};
+use std::io::Write;
+
+/// Assets smaller than this are not worth compressing.
+const MIN_COMPRESS_SIZE: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    /// Value of the `Content-Encoding` header.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+}
+
+/// Strong entity tag of an asset, derived from its content.
+pub fn etag(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    let hash = digest[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!("\"{}\"", hash)
+}
+
+pub fn content_type(path: &str) -> String {
+    let mime = mime_guess::from_path(path).first_or_octet_stream();
+    match mime.type_() {
+        mime_guess::mime::TEXT => format!("{}; charset=utf-8", mime.essence_str()),
+        _ => mime.essence_str().to_string(),
+    }
+}
+
+/// Whether the `If-None-Match` header value matches `etag`.
+pub fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    if_none_match
+        .split(',')
+        .map(|t| t.trim())
+        .any(|t| t == "*" || t.trim_start_matches("W/") == etag)
+}
+
+pub fn is_compressible(content_type: &str, length: usize) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    length >= MIN_COMPRESS_SIZE
+        && (essence.starts_with("text/")
+            || essence == "application/javascript"
+            || essence == "application/json"
+            || essence == "application/wasm"
+            || essence == "image/svg+xml")
+}
+
+/// Picks the encoding to respond with from an `Accept-Encoding` header by
+/// the quality values given for it, preferring brotli over gzip when the
+/// client accepts both equally.
+pub fn negotiate(accept_encoding: &str) -> Option<Encoding> {
+    let accepted = accept_encoding
+        .split(',')
+        .filter_map(|e| {
+            let mut parts = e.split(';').map(|p| p.trim());
+            let name = parts.next()?.to_lowercase();
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .map(|q| q.parse::<f32>().unwrap_or(0.0))
+                .unwrap_or(1.0);
+            Some((name, q))
+        })
+        .collect::<Vec<_>>();
+    // An encoding that is named takes its own quality over that of `*`.
+    let quality = |name: &str| {
+        let find = |n: &str| accepted.iter().find(|(a, _)| a == n).map(|(_, q)| *q);
+        find(name).or_else(|| find("*")).unwrap_or(0.0)
+    };
+    let (brotli, gzip) = (quality("br"), quality("gzip"));
+    if brotli > 0.0 && brotli >= gzip {
+        Some(Encoding::Brotli)
+    } else if gzip > 0.0 {
+        Some(Encoding::Gzip)
+    } else {
+        None
+    }
+}
+
+pub fn compress(content: &[u8], encoding: Encoding) -> Vec<u8> {
+    match encoding {
+        Encoding::Brotli => {
+            let mut compressed = Vec::new();
+            {
+                let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 9, 22);
+                writer.write_all(content).expect("brotli compress asset");
+            }
+            compressed
+        }
+        Encoding::Gzip => {
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(content).expect("gzip compress asset");
+            encoder.finish().expect("gzip compress asset")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn negotiate_follows_quality_values() {
+        assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0.5, gzip;q=0.8"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0.8, gzip;q=0.8"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("BR ; q=1.0"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("br;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("br;q=0, gzip;q=0"), None);
+        assert_eq!(negotiate("identity, deflate"), None);
+        assert_eq!(negotiate(""), None);
+        assert_eq!(negotiate("br;q=abc"), None);
+    }
+
+    #[test]
+    fn negotiate_takes_named_encodings_over_the_wildcard() {
+        assert_eq!(negotiate("*"), Some(Encoding::Brotli));
+        assert_eq!(negotiate("*;q=0.5, br;q=0.1"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*;q=0, gzip"), Some(Encoding::Gzip));
+        assert_eq!(negotiate("*;q=0"), None);
+    }
+
+    #[test]
+    fn if_none_match_accepts_lists_weak_tags_and_any() {
+        let tag = etag(b"content");
+        assert!(etag_matches(&tag, &tag));
+        assert!(etag_matches(&format!("\"other\", {}", tag), &tag));
+        assert!(etag_matches(&format!("W/{}", tag), &tag));
+        assert!(etag_matches("*", &tag));
+        assert!(!etag_matches("\"other\"", &tag));
+        assert!(!etag_matches(&etag(b"changed"), &tag));
+        assert!(!etag_matches("", &tag));
+    }
+
+    #[test]
+    fn compressed_assets_decompress_to_the_original() {
+        let content = b"let scrapnote = 1;\n".repeat(100);
+        let mut decoded = Vec::new();
+        flate2::read::GzDecoder::new(&compress(&content, Encoding::Gzip)[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&compress(&content, Encoding::Brotli)[..], 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, content);
+    }
+}
@@ -0,0 +1,186 @@
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::store::NoteStore;
+
+/// URL path attachments are served under. Links to attachments in notes start
+/// with this prefix followed by the attachment id.
+pub const URL_PREFIX: &str = common::api::ATTACHMENT_PREFIX;
+
+/// URL of the attachment `id` in `vault`. Links to the attachments of
+/// vaults other than the default one carry the vault segment.
+pub fn url(vault: &str, id: &str) -> String {
+    common::api::vault_path(vault, &format!("{}{}", URL_PREFIX, id))
+}
+
+/// Whether `path` is the URL path of an attachment in any vault.
+pub fn is_url_path(path: &str) -> bool {
+    let in_vault = path
+        .strip_prefix(common::api::VAULT_PREFIX)
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(_, rest)| format!("/api/{}", rest));
+    match in_vault {
+        Some(p) => p.starts_with(URL_PREFIX),
+        None => path.starts_with(URL_PREFIX),
+    }
+}
+
+/// Largest attachment that can be uploaded, in bytes.
+pub const MAX_SIZE: usize = 64 * 1024 * 1024;
+
+/// Size of the chunks attachments are streamed in.
+pub const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Stores `data` in the attachment directory and returns its id. Ids are
+/// derived from the content, so uploading the same file twice yields the same
+/// attachment. The extension comes from the uploaded file `name`, falling back
+/// to the one registered for `content_type`.
+pub fn store(
+    store: &dyn NoteStore,
+    config: &Config,
+    name: Option<&str>,
+    content_type: Option<&str>,
+    data: &[u8],
+) -> std::io::Result<String> {
+    let digest = Sha256::digest(data);
+    let hash = digest[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    let extension = name
+        .and_then(|n| n.rsplit_once('.'))
+        .map(|(_, e)| e.to_lowercase())
+        .filter(|e| !e.is_empty() && e.chars().all(|c| c.is_ascii_alphanumeric()))
+        .or_else(|| {
+            content_type
+                .and_then(mime_guess::get_mime_extensions_str)
+                .and_then(|e| e.first())
+                .map(|e| e.to_string())
+        })
+        .unwrap_or_else(|| "bin".to_string());
+    let id = format!("{}.{}", hash, extension);
+
+    let name = format!("{}/{}", config.attachment_directory, id);
+    if !store.exists(&name) {
+        store.write(&name, data)?;
+    }
+    Ok(id)
+}
+
+/// Name of the attachment `id` in the store, or `None` when `id` is not a
+/// valid id.
+pub fn name(config: &Config, id: &str) -> Option<String> {
+    let valid = !id.is_empty()
+        && !id.starts_with('.')
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '.');
+    if valid {
+        Some(format!("{}/{}", config.attachment_directory, id))
+    } else {
+        None
+    }
+}
+
+/// Whether attachments of `content_type` are safe to show in the app. Images
+/// other than SVG, audio and video can't run script.
+pub fn is_inline(content_type: &str) -> bool {
+    let media = content_type.split('/').next().unwrap_or("");
+    matches!(media, "image" | "audio" | "video") && content_type != "image/svg+xml"
+}
+
+pub fn content_type(id: &str) -> String {
+    mime_guess::from_path(id)
+        .first_or_octet_stream()
+        .essence_str()
+        .to_string()
+}
+
+/// Parses a single `Range: bytes=...` header value against a resource of
+/// `length` bytes into an inclusive byte range. Returns `Ok(None)` for headers
+/// that should be ignored (malformed or inverted ranges, multiple ranges,
+/// other units) and `Err(())` for unsatisfiable ones.
+pub fn parse_range(header: &str, length: u64) -> Result<Option<(u64, u64)>, ()> {
+    let spec = match header.trim().strip_prefix("bytes=") {
+        Some(s) if !s.contains(',') => s.trim(),
+        _ => return Ok(None),
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some((s, e)) => (s.trim(), e.trim()),
+        None => return Ok(None),
+    };
+    if start.is_empty() {
+        let suffix = match end.parse::<u64>() {
+            Ok(s) => s,
+            Err(_) => return Ok(None),
+        };
+        if suffix == 0 || length == 0 {
+            return Err(());
+        }
+        return Ok(Some((length.saturating_sub(suffix), length - 1)));
+    }
+    let start = match start.parse::<u64>() {
+        Ok(s) => s,
+        Err(_) => return Ok(None),
+    };
+    let end = match end {
+        "" => None,
+        e => match e.parse::<u64>() {
+            Ok(e) => Some(e),
+            Err(_) => return Ok(None),
+        },
+    };
+    if matches!(end, Some(e) if e < start) {
+        return Ok(None);
+    }
+    if start >= length {
+        return Err(());
+    }
+    Ok(Some((start, end.unwrap_or(u64::MAX).min(length - 1))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_range_reads_bounded_suffix_and_open_ended_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Ok(Some((0, 99))));
+        assert_eq!(parse_range("bytes=-100", 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range("bytes=-2000", 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_range("bytes=500-", 1000), Ok(Some((500, 999))));
+        assert_eq!(parse_range("bytes=900-1999", 1000), Ok(Some((900, 999))));
+    }
+
+    #[test]
+    fn parse_range_ignores_malformed_headers() {
+        for header in &[
+            "bytes=",
+            "bytes=-",
+            "bytes=abc-10",
+            "bytes=0-x",
+            "bytes=10-5",
+            "bytes=0-1,4-5",
+            "items=0-10",
+            "bytes=5",
+        ] {
+            assert_eq!(parse_range(header, 1000), Ok(None), "{}", header);
+        }
+    }
+
+    #[test]
+    fn parse_range_refuses_ranges_past_the_end() {
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=1000-1001", 1000), Err(()));
+        assert_eq!(parse_range("bytes=-0", 1000), Err(()));
+        assert_eq!(parse_range("bytes=-10", 0), Err(()));
+    }
+
+    #[test]
+    fn only_media_is_shown_inline() {
+        assert!(is_inline("image/png"));
+        assert!(is_inline("video/mp4"));
+        assert!(is_inline("audio/ogg"));
+        assert!(!is_inline("image/svg+xml"));
+        assert!(!is_inline("text/html"));
+        assert!(!is_inline("application/octet-stream"));
+    }
+}
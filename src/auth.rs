@@ -0,0 +1,163 @@
+use hyper::header::{COOKIE, HOST, ORIGIN};
+use hyper::{Body, Request};
+use std::path::PathBuf;
+
+/// Header API clients send the access token in.
+pub const TOKEN_HEADER: &str = "x-scrapnote-token";
+/// Cookie the frontend carries the access token in, so that it also covers
+/// `EventSource` and `<img>` requests that cannot set headers.
+pub const TOKEN_COOKIE: &str = "scrapnote_token";
+
+/// Host names the server answers to. Anything else in the `Host` header is
+/// likely a DNS rebinding attempt.
+const ALLOWED_HOSTS: &[&str] = &["127.0.0.1", "localhost", "[::1]"];
+
+/// Creates a random secret for this launch of the server.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).expect("generate access token");
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether the request targets this server by one of its local names and,
+/// for requests made by a web page, comes from the page the server serves.
+pub fn is_allowed_origin(req: &Request<Body>) -> bool {
+    let host = match req.headers().get(HOST).and_then(|v| v.to_str().ok()) {
+        Some(h) => h,
+        None => return false,
+    };
+    if !ALLOWED_HOSTS.contains(&host_name(host)) {
+        return false;
+    }
+    match req.headers().get(ORIGIN) {
+        Some(origin) => origin.to_str().ok() == Some(&format!("http://{}", host)),
+        None => true,
+    }
+}
+
+fn host_name(host: &str) -> &str {
+    if host.starts_with('[') {
+        match host.find(']') {
+            Some(end) => &host[..=end],
+            None => host,
+        }
+    } else {
+        host.split(':').next().unwrap_or(host)
+    }
+}
+
+/// Whether the request carries `token` in the token header or cookie.
+pub fn has_token(req: &Request<Body>, token: &str) -> bool {
+    let header = req
+        .headers()
+        .get(TOKEN_HEADER)
+        .and_then(|v| v.to_str().ok());
+    let cookie = req
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(name, _)| *name == TOKEN_COOKIE)
+        .map(|(_, value)| value);
+    header.or(cookie).map(|t| equals(t, token)).unwrap_or(false)
+}
+
+/// Token passed in the query when loading the frontend, e.g. the URL printed
+/// at startup or the one the webview window opens.
+pub fn query_token(req: &Request<Body>) -> Option<&str> {
+    req.uri()
+        .query()?
+        .split('&')
+        .find_map(|p| p.strip_prefix("token="))
+}
+
+pub fn token_cookie(token: &str) -> String {
+    format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Strict",
+        TOKEN_COOKIE, token
+    )
+}
+
+/// Compares in constant time so that the token cannot be guessed byte by
+/// byte from response times.
+pub fn equals(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+const TOKEN_FILE_PREFIX: &str = "scrapnote-";
+const TOKEN_FILE_SUFFIX: &str = ".token";
+
+fn token_directory() -> PathBuf {
+    crate::config::runtime_directory()
+}
+
+/// File the token of the server on `port` is written to, so that the
+/// `scrapnote` command line tool can talk to it.
+fn token_path(port: u16) -> PathBuf {
+    token_directory().join(format!(
+        "{}{}{}",
+        TOKEN_FILE_PREFIX, port, TOKEN_FILE_SUFFIX
+    ))
+}
+
+/// Ports of the servers that wrote a token file and haven't removed it yet,
+/// sorted.
+pub fn running_servers() -> Vec<u16> {
+    let entries = match std::fs::read_dir(token_directory()) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+    let mut ports = entries
+        .filter_map(|e| e.ok()?.file_name().into_string().ok())
+        .filter_map(|name| {
+            name.strip_prefix(TOKEN_FILE_PREFIX)?
+                .strip_suffix(TOKEN_FILE_SUFFIX)?
+                .parse()
+                .ok()
+        })
+        .collect::<Vec<u16>>();
+    ports.sort_unstable();
+    ports
+}
+
+/// Writes the token file readable only by the current user and returns its
+/// path.
+pub fn write_token_file(port: u16, token: &str) -> std::io::Result<PathBuf> {
+    use std::io::Write;
+    crate::config::create_private_directory(&token_directory())?;
+    let path = token_path(port);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = match options.open(&path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+            // Left behind by a server on this port that didn't stop cleanly.
+            std::fs::remove_file(&path)?;
+            options.open(&path)?
+        }
+        Err(e) => return Err(e),
+    };
+    file.write_all(token.as_bytes())?;
+    Ok(path)
+}
+
+pub fn read_token_file(port: u16) -> Option<String> {
+    std::fs::read_to_string(token_path(port))
+        .ok()
+        .map(|t| t.trim().to_string())
+}
+
+pub(crate) fn remove_token_file(port: u16) {
+    let _ = std::fs::remove_file(token_path(port));
+}
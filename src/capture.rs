@@ -0,0 +1,128 @@
+use chrono::Local;
+
+use common::links;
+
+use crate::config::Config;
+use crate::store::NoteStore;
+
+/// Appends `text` to `note` as a timestamped list item and returns the name of
+/// the note written. When `heading` is given the entry goes to the end of that
+/// section, which is created at the end of the note if it does not exist yet.
+pub fn append(
+    store: &dyn NoteStore,
+    config: &Config,
+    note: Option<&str>,
+    heading: Option<&str>,
+    text: &str,
+) -> std::io::Result<String> {
+    let name = note.unwrap_or(&config.capture_note).to_string();
+    let content = match store.read_to_string(&name) {
+        Ok(c) => c,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let entry = format_entry(text);
+    let content = match heading {
+        Some(h) => insert_under_heading(&content, h, &entry),
+        None => append_block(&content, &entry),
+    };
+    store.write(&name, content.as_bytes())?;
+    Ok(name)
+}
+
+fn format_entry(text: &str) -> String {
+    let stamp = Local::now().format("%Y-%m-%d %H:%M");
+    let mut lines = text.trim_end().lines();
+    let mut entry = format!("- {} {}\n", stamp, lines.next().unwrap_or(""));
+    for line in lines {
+        entry.push_str("  ");
+        entry.push_str(line);
+        entry.push('\n');
+    }
+    entry
+}
+
+fn append_block(content: &str, block: &str) -> String {
+    let mut content = content.to_string();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(block);
+    content
+}
+
+fn insert_under_heading(content: &str, heading: &str, entry: &str) -> String {
+    let headings = links::headings(content);
+    let (index, found) = match headings
+        .iter()
+        .enumerate()
+        .find(|(_, h)| h.title == heading)
+    {
+        Some(h) => h,
+        None => {
+            let section = format!("\n## {}\n\n{}", heading, entry);
+            return append_block(content, &section);
+        }
+    };
+    let end = headings[index + 1..]
+        .iter()
+        .find(|h| h.level <= found.level)
+        .map(|h| h.start)
+        .unwrap_or_else(|| content.len());
+    // The entry goes after the last line of the section that isn't blank.
+    let last = content[..end].trim_end().len().max(found.start);
+    let body_end = last
+        + content[last..end]
+            .find(['\r', '\n'])
+            .unwrap_or(end - last);
+    let rest = &content[body_end..];
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+        .unwrap_or(rest);
+    format!("{}\n{}{}", &content[..body_end], entry, rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+
+    fn capture(store: &InMemoryStore, heading: Option<&str>, text: &str) -> String {
+        let config = Config::default();
+        let name = append(store, &config, Some("log.md"), heading, text).unwrap();
+        store.read_to_string(&name).unwrap()
+    }
+
+    #[test]
+    fn appends_to_the_end_of_the_note() {
+        let store = InMemoryStore::new();
+        store.write("log.md", b"# Log").unwrap();
+        let content = capture(&store, None, "first\nsecond");
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], "# Log");
+        assert!(lines[1].starts_with("- ") && lines[1].ends_with(" first"));
+        assert_eq!(lines[2], "  second");
+    }
+
+    #[test]
+    fn inserts_at_the_end_of_the_section() {
+        let store = InMemoryStore::new();
+        let note = "# Log\n## Todo\n- a\n```\n## Not a heading\n```\n\n## Done\n- b\n";
+        store.write("log.md", note.as_bytes()).unwrap();
+        let content = capture(&store, Some("Todo"), "new");
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[5], "```");
+        assert!(lines[6].starts_with("- ") && lines[6].ends_with(" new"));
+        assert_eq!(&lines[7..], &["", "## Done", "- b"]);
+    }
+
+    #[test]
+    fn creates_missing_section() {
+        let store = InMemoryStore::new();
+        let content = capture(&store, Some("Inbox"), "new");
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(&lines[..3], &["", "## Inbox", ""]);
+        assert!(lines[3].ends_with(" new"));
+    }
+}
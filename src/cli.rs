@@ -0,0 +1,570 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::io::{ErrorKind, IsTerminal, Read};
+use std::process::Command;
+
+use common::api;
+use common::links;
+use common::{CaptureRequest, ItemType, RenameFileRequest, SaveFileContentRequest, DEFAULT_VAULT};
+use scrapnote::{scratch, Client, Config, ExportOptions, FsStore, NoteStore};
+
+const USAGE: &str = "usage:
+    scrapnote ls [KEY]
+    scrapnote cat NAME
+    scrapnote new [NAME]
+    scrapnote edit NAME
+    scrapnote rm NAME
+    scrapnote mv FROM TO
+    scrapnote search QUERY
+    scrapnote tags [TAG]
+    scrapnote capture [--port PORT] [--token TOKEN] [--vault VAULT] [--note NOTE]
+                      [--heading HEADING] [TEXT...]
+    scrapnote export --html OUT [--dir DIR] [--vault VAULT] [--tag TAG]
+                     [--folder FOLDER]
+    scrapnote lsp [--dir DIR]
+
+The note commands work on the files in DIR when given --dir DIR (the
+configured notes directory by default), or on a running server when given
+--server, --port PORT, --token TOKEN or --vault VAULT. --vault selects one of
+the server's vaults instead of the default one.
+new and capture read text from stdin when it is not a terminal or no TEXT is
+given. new without NAME creates a scratch note that is named after its first
+heading and prints the name.
+edit opens the note in $VISUAL or $EDITOR and saves it if it was changed.
+search prints the lines that contain QUERY, ignoring case.
+tags counts the notes carrying each tag, or lists the notes carrying TAG.
+The access token of the server is read from the file it writes at startup
+unless --token or SCRAPNOTE_TOKEN is given. Without --port, the server is
+found from these files when only one is running. The webview window serves
+the API, and writes the file, only when started with --http-api.
+export renders notes in DIR to a static site in OUT. --vault names the vault
+DIR is served as, so that links to its attachments are copied along.
+lsp serves the Language Server Protocol on stdin and stdout for the notes in
+DIR, or in the workspace the editor opens when DIR is not given.";
+
+/// Options selecting the notes a note command works on.
+const NOTEBOOK_OPTIONS: &[&str] = &["--dir", "--port", "--token", "--vault"];
+
+type Result<T> = std::result::Result<T, Box<dyn Error + Send + Sync>>;
+
+#[tokio::main]
+async fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let result = match args.first().map(|a| a.as_str()) {
+        Some("ls") => ls(&args[1..]).await,
+        Some("cat") => cat(&args[1..]).await,
+        Some("new") => new(&args[1..]).await,
+        Some("edit") => edit(&args[1..]).await,
+        Some("rm") => rm(&args[1..]).await,
+        Some("mv") => mv(&args[1..]).await,
+        Some("search") => search(&args[1..]).await,
+        Some("tags") => tags(&args[1..]).await,
+        Some("capture") => capture(&args[1..]).await,
+        Some("export") => export(&args[1..]),
+        Some("lsp") => lsp(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = result {
+        eprintln!("scrapnote: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn ls(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let key = match args.positional.as_slice() {
+        [] => "",
+        [key] => key.as_str(),
+        _ => return Err(USAGE.into()),
+    };
+    for name in Notebook::open(&args)?.list(key).await? {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+async fn cat(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let name = args.one()?;
+    print!("{}", Notebook::open(&args)?.read(name).await?);
+    Ok(())
+}
+
+async fn new(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let name = match args.positional.as_slice() {
+        [] => None,
+        [name] => Some(name.as_str()),
+        _ => return Err(USAGE.into()),
+    };
+    let mut content = String::new();
+    if !std::io::stdin().is_terminal() {
+        std::io::stdin().read_to_string(&mut content)?;
+    }
+    let name = Notebook::open(&args)?.create(name, content).await?;
+    println!("{}", name);
+    Ok(())
+}
+
+async fn edit(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let name = args.one()?;
+    let notebook = Notebook::open(&args)?;
+    let (content, modified) = notebook.read_with_modified(name).await?;
+
+    // Keeps the file name, so that the editor recognizes the file type.
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let copy = format!("edit-{}-{}", std::process::id(), file_name);
+    let path = scrapnote::write_private_file(&copy, content.as_bytes())?;
+    let edited = match run_editor(&path).and_then(|_| Ok(std::fs::read_to_string(&path)?)) {
+        Ok(e) => e,
+        Err(e) => {
+            let _ = std::fs::remove_file(&path);
+            return Err(e);
+        }
+    };
+    if edited != content {
+        // Refused if the note was changed while it was being edited.
+        if let Err(e) = notebook.save(name, edited, modified).await {
+            return Err(format!("{}; the edited note is kept in {}", e, path.display()).into());
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+/// Opens `path` in the user's editor and waits for it to exit.
+fn run_editor(path: &std::path::Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Through the shell, so that the editor may be given with arguments.
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(path)
+        .status()?;
+    if !status.success() {
+        return Err(format!("{} exited with {}; the note was not saved", editor, status).into());
+    }
+    Ok(())
+}
+
+async fn rm(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    Notebook::open(&args)?.delete(args.one()?).await
+}
+
+async fn mv(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let (from, to) = match args.positional.as_slice() {
+        [from, to] => (from, to),
+        _ => return Err(USAGE.into()),
+    };
+    Notebook::open(&args)?.rename(from, to).await
+}
+
+async fn search(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let query = args.one()?.to_lowercase();
+    let notebook = Notebook::open(&args)?;
+    for name in notebook.list("").await? {
+        let content = notebook.read(&name).await?;
+        for (number, line) in content.lines().enumerate() {
+            if line.to_lowercase().contains(&query) {
+                println!("{}:{}: {}", name, number + 1, line);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn tags(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, NOTEBOOK_OPTIONS, &["--server"])?;
+    let tag = match args.positional.as_slice() {
+        [] => None,
+        [tag] => Some(tag.trim_start_matches('#')),
+        _ => return Err(USAGE.into()),
+    };
+    let notebook = Notebook::open(&args)?;
+    let mut counts = BTreeMap::new();
+    for name in notebook.list("").await? {
+        let content = notebook.read(&name).await?;
+        let mut tags = links::tags(&content)
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        match tag {
+            Some(tag) => {
+                if tags.iter().any(|t| t == tag) {
+                    println!("{}", name);
+                }
+            }
+            None => {
+                for t in tags {
+                    *counts.entry(t).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+    for (tag, count) in counts {
+        println!("#{}\t{}", tag, count);
+    }
+    Ok(())
+}
+
+async fn capture(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &["--port", "--token", "--vault", "--note", "--heading"],
+        &[],
+    )?;
+    let text = if args.positional.is_empty() {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        args.positional.join(" ")
+    };
+    if text.trim().is_empty() {
+        return Err("nothing to capture".into());
+    }
+
+    let client = args.client()?;
+    let response = client
+        .call(api::Capture(CaptureRequest {
+            text,
+            note: args.options.get("--note").cloned(),
+            heading: args.options.get("--heading").cloned(),
+        }))
+        .await?;
+    println!("{}", response.name);
+    Ok(())
+}
+
+fn export(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &["--html", "--dir", "--vault", "--tag", "--folder"],
+        &[],
+    )?;
+    let out = args
+        .options
+        .get("--html")
+        .ok_or_else(|| format!("missing --html OUT\n{}", USAGE))?;
+    let mut config = Config::default();
+    if let Some(d) = args.options.get("--dir") {
+        config.file_directory = d.to_string();
+    }
+    let options = ExportOptions {
+        out_directory: out.into(),
+        tag: args.options.get("--tag").cloned(),
+        folder: args.options.get("--folder").cloned(),
+        vault: args
+            .options
+            .get("--vault")
+            .cloned()
+            .unwrap_or_else(|| DEFAULT_VAULT.to_string()),
+    };
+    let store = FsStore::new(&config.file_directory);
+    scrapnote::export_html(&store, &config, &options)?;
+    Ok(())
+}
+
+/// The notes a command works on: the files in a directory, or a running
+/// server. Both behave the same, since the server keeps its notes in an
+/// [`FsStore`] as well.
+enum Notebook {
+    Local { store: FsStore, config: Config },
+    Remote(Client),
+}
+
+impl Notebook {
+    fn open(args: &Args) -> Result<Self> {
+        let remote = args.switches.iter().any(|s| s == "--server")
+            || args.options.contains_key("--port")
+            || args.options.contains_key("--token")
+            || args.options.contains_key("--vault");
+        if remote {
+            if args.options.contains_key("--dir") {
+                return Err("--dir cannot be used with a server".into());
+            }
+            return Ok(Notebook::Remote(args.client()?));
+        }
+        let mut config = Config::default();
+        if let Some(d) = args.options.get("--dir") {
+            config.file_directory = d.to_string();
+        }
+        Ok(Notebook::Local {
+            store: FsStore::new(&config.file_directory),
+            config,
+        })
+    }
+
+    /// Names of the notes in the notes directory and the inbox that contain
+    /// `key`, sorted.
+    async fn list(&self, key: &str) -> Result<Vec<String>> {
+        let mut names = match self {
+            Notebook::Local { store, config } => {
+                let mut names = Vec::new();
+                for directory in &["", config.inbox_directory.as_str()] {
+                    store.visit(directory, &mut |entry| {
+                        if !entry.is_directory && entry.name.contains(key) {
+                            names.push(entry.name);
+                        }
+                        true
+                    })?;
+                }
+                names
+            }
+            Notebook::Remote(client) => client
+                .call(api::ListFiles {
+                    key: key.to_string(),
+                })
+                .await?
+                .files
+                .into_iter()
+                .filter(|item| matches!(item.item_type, ItemType::File))
+                .map(|item| item.name)
+                .collect(),
+        };
+        names.sort();
+        Ok(names)
+    }
+
+    async fn read(&self, name: &str) -> Result<String> {
+        Ok(self.read_with_modified(name).await?.0)
+    }
+
+    /// Content of `name` and its modification time, for [`Notebook::save`].
+    async fn read_with_modified(&self, name: &str) -> Result<(String, Option<u64>)> {
+        let read = match self {
+            Notebook::Local { store, .. } => store
+                .read_to_string(name)
+                .map(|c| (c, store.stat(name).ok().and_then(|m| m.modified_millis())))
+                .map_err(|e| e.into()),
+            Notebook::Remote(client) => client
+                .call(api::GetFile {
+                    name: name.to_string(),
+                    create_missing: false,
+                })
+                .await
+                .map(|r| (r.content, r.modified)),
+        };
+        read.map_err(|e| format!("{}: {}", name, e).into())
+    }
+
+    /// Replaces the content of `name`, unless it was modified since
+    /// `expected_modified` when that is given.
+    async fn save(
+        &self,
+        name: &str,
+        content: String,
+        expected_modified: Option<u64>,
+    ) -> Result<()> {
+        match self {
+            Notebook::Local { store, .. } => {
+                if let Some(expected) = expected_modified {
+                    let modified = store.stat(name).ok().and_then(|m| m.modified_millis());
+                    if modified != Some(expected) {
+                        return Err(format!("{} was changed since it was read", name).into());
+                    }
+                }
+                store.write(name, content.as_bytes())?;
+            }
+            Notebook::Remote(client) => {
+                client
+                    .call(api::SaveFile {
+                        name: name.to_string(),
+                        request: SaveFileContentRequest {
+                            content,
+                            autosave: true,
+                            create_new: false,
+                            expected_modified,
+                        },
+                    })
+                    .await
+                    .map_err(|e| format!("{}: {}", name, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a note named `name`, or a scratch note that is renamed after
+    /// its first heading, and returns its name.
+    async fn create(&self, name: Option<&str>, content: String) -> Result<String> {
+        let name = match self {
+            Notebook::Local { store, config } => match name {
+                Some(name) => {
+                    store.create(name).map_err(|e| already_exists(name, e))?;
+                    store.write(name, content.as_bytes())?;
+                    Some(name.to_string())
+                }
+                None => {
+                    let name = scratch::create(store, config)?;
+                    store.write(&name, content.as_bytes())?;
+                    scratch::on_saved(store, config, &name, &content)?
+                }
+            },
+            Notebook::Remote(client) => {
+                let (name, autosave) = match name {
+                    Some(name) => (name.to_string(), true),
+                    None => (client.call(api::CreateScratch).await?.name, false),
+                };
+                client
+                    .call(api::SaveFile {
+                        name,
+                        request: SaveFileContentRequest {
+                            content,
+                            autosave,
+                            create_new: autosave,
+                            expected_modified: None,
+                        },
+                    })
+                    .await?
+                    .name
+            }
+        };
+        name.ok_or_else(|| "an empty scratch note is not kept".into())
+    }
+
+    async fn delete(&self, name: &str) -> Result<()> {
+        match self {
+            Notebook::Local { store, .. } => store.delete(name)?,
+            Notebook::Remote(client) => {
+                client
+                    .call(api::DeleteFile {
+                        name: name.to_string(),
+                    })
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn rename(&self, from: &str, to: &str) -> Result<()> {
+        match self {
+            Notebook::Local { store, .. } => {
+                if store.exists(to) {
+                    return Err(format!("{} already exists", to).into());
+                }
+                store.rename(from, to)?;
+            }
+            Notebook::Remote(client) => {
+                client
+                    .call(api::RenameFile(RenameFileRequest {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                    }))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn already_exists(name: &str, e: std::io::Error) -> Box<dyn Error + Send + Sync> {
+    if e.kind() == ErrorKind::AlreadyExists {
+        format!("{} already exists", name).into()
+    } else {
+        e.into()
+    }
+}
+
+fn lsp(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--dir"], &[])?;
+    scrapnote::run_language_server(args.options.get("--dir").map(|d| d.into()))?;
+    Ok(())
+}
+
+struct Args {
+    options: HashMap<String, String>,
+    switches: Vec<String>,
+    positional: Vec<String>,
+}
+
+impl Args {
+    /// Splits `args` into `--name value` pairs for the `known` options, the
+    /// given `switches` that take no value and positional arguments.
+    fn parse(args: &[String], known: &[&str], switches: &[&str]) -> Result<Self> {
+        let mut options = HashMap::new();
+        let mut found = Vec::new();
+        let mut positional = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if switches.contains(&arg.as_str()) {
+                found.push(arg.to_string());
+            } else if known.contains(&arg.as_str()) {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("missing value for {}", arg))?;
+                options.insert(arg.to_string(), value.to_string());
+            } else if arg.starts_with("--") {
+                return Err(format!("unknown option {}\n{}", arg, USAGE).into());
+            } else {
+                positional.push(arg.to_string());
+            }
+        }
+        Ok(Args {
+            options,
+            switches: found,
+            positional,
+        })
+    }
+
+    /// The only positional argument.
+    fn one(&self) -> Result<&str> {
+        match self.positional.as_slice() {
+            [arg] => Ok(arg),
+            _ => Err(USAGE.into()),
+        }
+    }
+
+    /// Port given by `--port`, or else of the one server running on this
+    /// machine, as found from the token files.
+    fn port(&self) -> Result<u16> {
+        if let Some(p) = self.options.get("--port") {
+            return Ok(p.parse()?);
+        }
+        match scrapnote::auth::running_servers().as_slice() {
+            [port] => Ok(*port),
+            [] => Err("no running server found; pass --port".into()),
+            ports => {
+                let ports = ports.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+                Err(format!(
+                    "several servers are running, on ports {}; pass --port",
+                    ports.join(", ")
+                )
+                .into())
+            }
+        }
+    }
+
+    fn token(&self) -> Result<String> {
+        if let Some(t) = self.options.get("--token") {
+            return Ok(t.to_string());
+        }
+        if let Ok(t) = std::env::var("SCRAPNOTE_TOKEN") {
+            return Ok(t);
+        }
+        let port = self.port()?;
+        scrapnote::auth::read_token_file(port)
+            .ok_or_else(|| format!("no access token for port {}; pass --token", port).into())
+    }
+
+    /// A client for the server and vault given by `--port`, `--token` and
+    /// `--vault`.
+    fn client(&self) -> Result<Client> {
+        let client = Client::new(self.port()?, self.token()?);
+        Ok(match self.options.get("--vault") {
+            Some(v) => client.vault(v.as_str()),
+            None => client,
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request};
+
+use common::api::Endpoint;
+
+use crate::auth::TOKEN_HEADER;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Calls the API of a running server, e.g. from the command line tool or
+/// from integration tests.
+pub struct Client {
+    base: String,
+    token: String,
+    vault: String,
+    http: hyper::Client<hyper::client::HttpConnector>,
+}
+
+impl Client {
+    /// A client for the server on `port` of this machine, authenticating
+    /// with its access `token`.
+    pub fn new(port: u16, token: impl Into<String>) -> Self {
+        Client {
+            base: format!("http://127.0.0.1:{}", port),
+            token: token.into(),
+            vault: common::DEFAULT_VAULT.to_string(),
+            http: hyper::Client::new(),
+        }
+    }
+
+    /// Makes the calls on the notes of the vault `name` instead of the
+    /// default vault.
+    pub fn vault(mut self, name: impl Into<String>) -> Self {
+        self.vault = name.into();
+        self
+    }
+
+    pub async fn call<E: Endpoint>(&self, endpoint: E) -> Result<E::Response> {
+        let builder = Request::builder()
+            .method(E::METHOD.as_str())
+            .uri(format!("{}{}", self.base, endpoint.path_in(&self.vault)))
+            .header(TOKEN_HEADER, self.token.as_str());
+        let request = match endpoint.body() {
+            Some(body) => builder
+                .header(CONTENT_TYPE, body.content_type)
+                .body(Body::from(body.data))?,
+            None => builder.body(Body::empty())?,
+        };
+        let response = self.http.request(request).await?;
+        if !response.status().is_success() {
+            return Err(format!("server responded with {}", response.status()).into());
+        }
+        let body = hyper::body::to_bytes(response.into_body()).await?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+}
@@ -1,11 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
 pub struct Config {
+    /// Directory the notes are kept in by the default [`crate::FsStore`].
     pub file_directory: String,
+    /// Further notebooks, each in a directory of its own. The notes in
+    /// `file_directory` make up the vault named [`common::DEFAULT_VAULT`].
+    pub vaults: Vec<VaultConfig>,
+    /// Directory for scratch notes, in the note store.
+    pub inbox_directory: String,
+    /// Directory for uploaded attachments, in the note store.
+    pub attachment_directory: String,
+    /// Note that `/api/capture` appends to when no target is given.
+    pub capture_note: String,
+    /// Directory `/api/export` writes the site to, in a subdirectory named
+    /// after the vault for vaults other than the default one. Exporting
+    /// through the API is refused when this is `None`.
+    pub export_directory: Option<String>,
+    /// Program that opens notes in an external editor, run through the shell
+    /// with the path of a copy of the note appended. It must not exit before
+    /// editing is done, e.g. `code --wait` or `gvim -f`. `$VISUAL` and
+    /// `$EDITOR` are used when this is `None`.
+    pub external_editor: Option<String>,
+    /// Serves the app to other machines on the network. The server only
+    /// listens on localhost when this is `None`.
+    pub lan: Option<LanConfig>,
+    /// The window of the `webview` binary.
+    pub window: WindowConfig,
+}
+
+pub struct LanConfig {
+    /// Address to listen on, e.g. `0.0.0.0:8443`.
+    pub address: String,
+    /// Password asked for on the login page.
+    pub password: String,
+    /// PEM file with the certificate chain. A self-signed certificate is
+    /// generated on first run when neither this nor `private_key` is given.
+    pub certificate: Option<String>,
+    /// PEM file with the private key of `certificate`.
+    pub private_key: Option<String>,
+}
+
+pub struct VaultConfig {
+    /// Name the vault is selected by, in the `/api/vault/{name}` segment of
+    /// API paths.
+    pub name: String,
+    pub directory: String,
+}
+
+impl VaultConfig {
+    /// Parses `NAME=DIRECTORY`, as given on the command line.
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (name, directory) = arg.split_once('=')?;
+        if name.is_empty() || name == common::DEFAULT_VAULT || directory.is_empty() {
+            return None;
+        }
+        Some(VaultConfig {
+            name: name.to_string(),
+            directory: directory.to_string(),
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct WindowConfig {
+    /// Size of the window when no size is remembered from the last run.
+    pub width: usize,
+    pub height: usize,
+    pub resizable: bool,
+    /// Enables the web inspector. Only debug builds enable it by default.
+    pub devtools: bool,
 }
 
 impl Config {
     pub fn default() -> Self {
         Config {
             file_directory: "/home/inab/tmp/note".to_string(),
+            vaults: Vec::new(),
+            inbox_directory: "inbox".to_string(),
+            attachment_directory: "attachments".to_string(),
+            capture_note: "inbox.md".to_string(),
+            export_directory: None,
+            external_editor: None,
+            lan: None,
+            window: WindowConfig {
+                width: 500,
+                height: 600,
+                resizable: true,
+                devtools: cfg!(debug_assertions),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+struct WindowSize {
+    width: usize,
+    height: usize,
+}
+
+/// Size of the window when it was last closed, as remembered by
+/// [`save_window_size`].
+pub fn saved_window_size() -> Option<(usize, usize)> {
+    let data = std::fs::read(config_directory().join("window.json")).ok()?;
+    let size: WindowSize = serde_json::from_slice(&data).ok()?;
+    Some((size.width, size.height))
+}
+
+pub fn save_window_size(width: usize, height: usize) -> std::io::Result<()> {
+    let directory = config_directory();
+    std::fs::create_dir_all(&directory)?;
+    let data = serde_json::to_vec(&WindowSize { width, height }).expect("serialize WindowSize");
+    std::fs::write(directory.join("window.json"), data)
+}
+
+/// Directory for files scrapnote keeps between runs.
+pub(crate) fn config_directory() -> PathBuf {
+    match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(d) => PathBuf::from(d).join("scrapnote"),
+        None => PathBuf::from(std::env::var_os("HOME").unwrap_or_default())
+            .join(".config")
+            .join("scrapnote"),
+    }
+}
+
+/// Directory for files of running servers that only the current user may
+/// read, such as access tokens. Use [`create_private_directory`] before
+/// writing to it.
+pub fn runtime_directory() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(d) => PathBuf::from(d).join("scrapnote"),
+        None => config_directory().join("run"),
+    }
+}
+
+/// Creates the file `name` in [`runtime_directory`], readable only by the
+/// current user, with `content` and returns its path. Fails if the file
+/// exists.
+pub fn write_private_file(name: &str, content: &[u8]) -> std::io::Result<PathBuf> {
+    use std::io::Write;
+    let directory = runtime_directory();
+    create_private_directory(&directory)?;
+    let path = directory.join(name);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+    if let Err(e) = file.write_all(content) {
+        let _ = std::fs::remove_file(&path);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Creates `directory` accessible only to the current user. Fails if it
+/// already exists and others can access it, rather than leaving files there.
+pub fn create_private_directory(directory: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+        builder.mode(0o700);
+        builder.create(directory)?;
+        let metadata = std::fs::symlink_metadata(directory)?;
+        if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("{} is accessible to other users", directory.display()),
+            ));
         }
+        Ok(())
     }
+    #[cfg(not(unix))]
+    builder.create(directory)
 }
@@ -0,0 +1,335 @@
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, CONTROLS};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use crate::attachment;
+use crate::config::Config;
+use crate::index::{Index, Note};
+use crate::store::NoteStore;
+use common::{links, markdown};
+
+/// Characters escaped in a path segment of an exported URL.
+const SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Directory of the export that the pages of the notes and the files they
+/// link to are written to, so that no note can overwrite `index.html` or
+/// `search.json`.
+const EXPORTED_NOTES: &str = "notes";
+
+/// Directory of the export that uploaded attachments are copied to.
+const EXPORTED_ATTACHMENTS: &str = "attachments";
+
+const STYLE: &str = "
+body { max-width: 760px; margin: 24px auto; padding: 0 16px; font-family: \"Roboto\", \"Noto Sans JP\", sans-serif; line-height: 1.6; }
+nav { margin-bottom: 16px; font-size: 0.9em; }
+pre { padding: 8px; overflow-x: auto; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid lightgray; padding: 2px 6px; }
+.wikilink.missing { color: gray; }
+.tags span, .tag { margin-right: 6px; color: gray; }
+.backlinks { margin-top: 32px; border-top: 1px solid lightgray; }
+";
+
+pub struct ExportOptions {
+    pub out_directory: PathBuf,
+    /// Only export notes with this tag.
+    pub tag: Option<String>,
+    /// Only export notes below this folder.
+    pub folder: Option<String>,
+    /// Vault the notes belong to, which links to attachments point into.
+    pub vault: String,
+}
+
+#[derive(Serialize)]
+struct SearchEntry<'a> {
+    title: &'a str,
+    url: String,
+    tags: &'a [String],
+    text: &'a str,
+}
+
+/// Renders the notes selected by `options` to a static site with an index
+/// page, backlinks and a `search.json`. Wiki links between exported notes
+/// become relative URLs; attachments and local files linked from the notes
+/// are copied along. Returns the number of exported notes.
+pub fn export_html(
+    store: &dyn NoteStore,
+    config: &Config,
+    options: &ExportOptions,
+) -> std::io::Result<usize> {
+    let index = Index::scan(store)?;
+    let selected = index
+        .notes
+        .iter()
+        .filter(|n| is_selected(n, options))
+        .collect::<Vec<_>>();
+    let selected_names = selected
+        .iter()
+        .map(|n| n.name.as_str())
+        .collect::<HashSet<_>>();
+
+    let out = &options.out_directory;
+    std::fs::create_dir_all(out)?;
+    let mut copied = HashSet::new();
+    let attachment_prefix = attachment::url(&options.vault, "");
+    let backlinks = index.backlinks();
+    for note in &selected {
+        let page = page_path(&note.name);
+        let resolve = |target: &str| {
+            index
+                .resolve(target)
+                .filter(|t| selected_names.contains(t.name.as_str()))
+                .map(|t| relative_url(&page, &page_path(&t.name)))
+        };
+        let body = markdown::render_with_links(&note.content, &resolve);
+        let body = rewrite_attachment_urls(&body, &attachment_prefix, &page);
+        let backlinks = backlinks
+            .get(note.name.as_str())
+            .into_iter()
+            .flatten()
+            .filter(|b| selected_names.contains(b.name.as_str()))
+            .copied()
+            .collect::<Vec<_>>();
+        let html = render_page(note, &page, &body, &backlinks);
+        let path = out.join(&page);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, html)?;
+        copy_attachments(store, config, note, &attachment_prefix, out, &mut copied)?;
+    }
+
+    std::fs::write(out.join("index.html"), render_index(&selected))?;
+    let search = selected
+        .iter()
+        .map(|n| SearchEntry {
+            title: &n.title,
+            url: relative_url("index.html", &page_path(&n.name)),
+            tags: &n.tags,
+            text: &n.content,
+        })
+        .collect::<Vec<_>>();
+    let search = serde_json::to_string(&search).expect("serialize search index");
+    std::fs::write(out.join("search.json"), search)?;
+    println!("exported {} notes to {}", selected.len(), out.display());
+    Ok(selected.len())
+}
+
+fn is_selected(note: &Note, options: &ExportOptions) -> bool {
+    let in_folder = match &options.folder {
+        Some(f) => note
+            .name
+            .starts_with(&format!("{}/", f.trim_end_matches('/'))),
+        None => true,
+    };
+    let has_tag = match &options.tag {
+        Some(t) => note.tags.iter().any(|n| n == t.trim_start_matches('#')),
+        None => true,
+    };
+    in_folder && has_tag
+}
+
+/// Path of the exported page of a note, relative to the output directory.
+fn page_path(name: &str) -> String {
+    format!("{}/{}.html", EXPORTED_NOTES, links::stem(name))
+}
+
+/// URL of `target` relative to the page at `from`. Both are paths relative to
+/// the output directory.
+fn relative_url(from: &str, target: &str) -> String {
+    let depth = from.matches('/').count();
+    let encoded = target
+        .split('/')
+        .map(|s| utf8_percent_encode(s, SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+    format!("{}{}", "../".repeat(depth), encoded)
+}
+
+/// Points links to uploaded attachments at their copies in the export.
+fn rewrite_attachment_urls(body: &str, prefix: &str, page: &str) -> String {
+    let target = relative_url(page, &format!("{}/", EXPORTED_ATTACHMENTS));
+    body.replace(&format!("src=\"{}", prefix), &format!("src=\"{}", target))
+        .replace(&format!("href=\"{}", prefix), &format!("href=\"{}", target))
+}
+
+fn copy_attachments(
+    store: &dyn NoteStore,
+    config: &Config,
+    note: &Note,
+    prefix: &str,
+    out: &Path,
+    copied: &mut HashSet<PathBuf>,
+) -> std::io::Result<()> {
+    let note_directory = Path::new(&note.name)
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default();
+    for destination in markdown::link_destinations(&note.content) {
+        let (source, relative) = if let Some(id) = destination.strip_prefix(prefix) {
+            match attachment::name(config, id) {
+                Some(n) => (n, Path::new(EXPORTED_ATTACHMENTS).join(id)),
+                None => continue,
+            }
+        } else if destination.contains(':')
+            || destination.starts_with('/')
+            || destination.starts_with('#')
+        {
+            continue;
+        } else {
+            let destination = destination.split('#').next().unwrap_or("");
+            let destination = percent_decode_str(destination).decode_utf8_lossy();
+            match normalize(&note_directory.join(destination.as_ref())) {
+                Some(r) => match r.to_str() {
+                    Some(name) => (name.to_string(), Path::new(EXPORTED_NOTES).join(&r)),
+                    None => continue,
+                },
+                None => continue,
+            }
+        };
+        if copied.contains(&relative) {
+            continue;
+        }
+        let data = match store.read(&source) {
+            Ok(d) => d,
+            Err(_) => continue,
+        };
+        copied.insert(relative.clone());
+        let target = out.join(&relative);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&target, data)?;
+    }
+    Ok(())
+}
+
+/// Resolves `.` and `..` in a relative path, refusing paths that escape the
+/// notes directory.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::Normal(c) => normalized.push(c),
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(normalized)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_page(note: &Note, page: &str, body: &str, backlinks: &[&Note]) -> String {
+    let mut html = String::new();
+    html.push_str("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{}</title>\n", escape(&note.title)));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str(&format!(
+        "<nav><a href=\"{}\">Index</a></nav>\n",
+        relative_url(page, "index.html")
+    ));
+    if !note.tags.is_empty() {
+        html.push_str("<div class=\"tags\">");
+        for tag in &note.tags {
+            html.push_str(&format!("<span>#{}</span>", escape(tag)));
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("<article>\n");
+    html.push_str(body);
+    html.push_str("</article>\n");
+    if !backlinks.is_empty() {
+        html.push_str("<section class=\"backlinks\">\n<h2>Backlinks</h2>\n<ul>\n");
+        for backlink in backlinks {
+            html.push_str(&format!(
+                "<li><a href=\"{}\">{}</a></li>\n",
+                relative_url(page, &page_path(&backlink.name)),
+                escape(&backlink.title)
+            ));
+        }
+        html.push_str("</ul>\n</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render_index(notes: &[&Note]) -> String {
+    let mut html = String::new();
+    html.push_str("<!doctype html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>scrapnote</title>\n");
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+    html.push_str("<h1>Notes</h1>\n<ul>\n");
+    for note in notes {
+        html.push_str(&format!(
+            "<li><a href=\"{}\">{}</a>",
+            relative_url("index.html", &page_path(&note.name)),
+            escape(&note.title)
+        ));
+        for tag in &note.tags {
+            html.push_str(&format!(" <span class=\"tag\">#{}</span>", escape(tag)));
+        }
+        html.push_str("</li>\n");
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+
+    #[test]
+    fn notes_named_like_the_index_keep_their_pages() {
+        let store = InMemoryStore::new();
+        store.write("index.md", b"# Home\n\n[[other]]\n").unwrap();
+        store.write("search.json.md", b"# Search\n").unwrap();
+        store.write("other.md", b"# Other\n").unwrap();
+        let out = std::env::temp_dir().join(format!("scrapnote-export-{}", std::process::id()));
+        let options = ExportOptions {
+            out_directory: out.clone(),
+            tag: None,
+            folder: None,
+            vault: common::DEFAULT_VAULT.to_string(),
+        };
+        let exported = export_html(&store, &Config::default(), &options);
+        let read = |path: &str| std::fs::read_to_string(out.join(path)).unwrap();
+        let (index, home, search, other) = (
+            read("index.html"),
+            read("notes/index.html"),
+            read("search.json"),
+            read("notes/other.html"),
+        );
+        std::fs::remove_dir_all(&out).unwrap();
+
+        assert_eq!(exported.unwrap(), 3);
+        assert!(index.contains("<h1>Notes</h1>"));
+        assert!(index.contains("href=\"notes/index.html\""));
+        assert!(home.contains("<h1>Home</h1>"));
+        assert!(home.contains("href=\"../index.html\""));
+        assert!(search.starts_with('['));
+        assert!(other.contains("href=\"../notes/index.html\""));
+    }
+}
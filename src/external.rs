@@ -0,0 +1,183 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::process::{Child, Command};
+
+use common::{FileEvent, FileEventKind};
+
+use crate::{AppState, Vault};
+
+/// How often the copy of the note is checked for saves while the editor
+/// runs.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Distinguishes the copies of notes that are open at the same time.
+static NEXT_COPY: AtomicUsize = AtomicUsize::new(0);
+
+/// Opens the note `name` of `vault` in the external editor. The editor works
+/// on a copy of the note, which is written back to the store whenever it is
+/// saved and once more when the editor exits. Fails with
+/// `ErrorKind::AlreadyExists` if the note is open in an editor already.
+pub async fn open(state: &Arc<AppState>, vault: &Vault, name: &str) -> std::io::Result<()> {
+    // Saves check for open editors under the write lock, so none of them
+    // lands between copying the note and marking it as open.
+    let _guard = state.lock_writes().await;
+    let key = (vault.name.clone(), name.to_string());
+    if !state.external_edits.lock().unwrap().insert(key.clone()) {
+        return Err(Error::new(ErrorKind::AlreadyExists, name.to_string()));
+    }
+    let (child, path) = match start(state, vault, name).await {
+        Ok(started) => started,
+        Err(e) => {
+            state.external_edits.lock().unwrap().remove(&key);
+            return Err(e);
+        }
+    };
+    println!("opened in external editor: {}", name);
+    notify(state, FileEventKind::EditingStarted, vault, name);
+    tokio::spawn(sync(
+        state.clone(),
+        vault.clone(),
+        name.to_string(),
+        child,
+        path,
+    ));
+    Ok(())
+}
+
+async fn start(
+    state: &Arc<AppState>,
+    vault: &Vault,
+    name: &str,
+) -> std::io::Result<(Child, PathBuf)> {
+    let editor = editor(state)?;
+    let store = vault.store.clone();
+    let note = name.to_string();
+    // Keeps the file name, so that the editor recognizes the file type.
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+    let copy = format!(
+        "external-{}-{}-{}",
+        std::process::id(),
+        NEXT_COPY.fetch_add(1, Ordering::Relaxed),
+        file_name
+    );
+    let path = tokio::task::spawn_blocking(move || {
+        crate::config::write_private_file(&copy, &store.read(&note)?)
+    })
+    .await
+    .expect("copy note")?;
+    // Through the shell, so that the editor may be given with arguments.
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{} \"$1\"", editor))
+        .arg("sh")
+        .arg(&path)
+        .spawn();
+    match child {
+        Ok(c) => Ok((c, path)),
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&path).await;
+            Err(e)
+        }
+    }
+}
+
+fn editor(state: &AppState) -> std::io::Result<String> {
+    match &state.config.external_editor {
+        Some(e) => Ok(e.to_string()),
+        None => std::env::var("VISUAL")
+            .or_else(|_| std::env::var("EDITOR"))
+            .map_err(|_| Error::other("no external editor configured and $EDITOR is not set")),
+    }
+}
+
+/// Writes the copy at `path` back to the note `name` whenever it changes,
+/// until the editor exits or the server shuts down.
+async fn sync(state: Arc<AppState>, vault: Vault, name: String, mut child: Child, path: PathBuf) {
+    let shutdown = crate::wait_for(state.shutdown.clone());
+    tokio::pin!(shutdown);
+    let mut last_modified = modified(&path).await;
+    loop {
+        let mut done = false;
+        tokio::select! {
+            status = &mut child => {
+                match status {
+                    Ok(s) if !s.success() => println!("external editor of {} exited with {}", name, s),
+                    Ok(_) => {}
+                    Err(e) => println!("failed to wait for external editor of {}: {}", name, e),
+                }
+                done = true;
+            }
+            _ = &mut shutdown => done = true,
+            _ = tokio::time::delay_for(POLL_INTERVAL) => {}
+        }
+        let modified = modified(&path).await;
+        if modified != last_modified {
+            last_modified = modified;
+            if let Err(e) = write_back(&state, &vault, &name, &path).await {
+                println!("failed to save {} from external editor: {}", name, e);
+            }
+        }
+        if done {
+            break;
+        }
+    }
+    // The copy is left behind when the editor still runs at shutdown.
+    if !state.is_shutting_down() {
+        let _ = tokio::fs::remove_file(&path).await;
+    }
+    state
+        .external_edits
+        .lock()
+        .unwrap()
+        .remove(&(vault.name.clone(), name.clone()));
+    println!("closed in external editor: {}", name);
+    notify(&state, FileEventKind::EditingEnded, &vault, &name);
+}
+
+async fn write_back(
+    state: &Arc<AppState>,
+    vault: &Vault,
+    name: &str,
+    path: &Path,
+) -> std::io::Result<()> {
+    let content = tokio::fs::read(path).await?;
+    let _guard = state.lock_writes().await;
+    let store = vault.store.clone();
+    let name = name.to_string();
+    tokio::task::spawn_blocking(move || {
+        if store.read(&name).ok().as_ref() == Some(&content) {
+            return Ok(());
+        }
+        store.write(&name, &content)
+    })
+    .await
+    .expect("write note")
+}
+
+async fn modified(path: &Path) -> Option<SystemTime> {
+    tokio::fs::metadata(path)
+        .await
+        .and_then(|m| m.modified())
+        .ok()
+}
+
+fn notify(state: &AppState, kind: FileEventKind, vault: &Vault, name: &str) {
+    // Sending only fails when nobody is subscribed.
+    let _ = state.events.send(FileEvent {
+        kind,
+        name: name.to_string(),
+        vault: vault.name.clone(),
+    });
+}
+
+/// Whether the note `name` of `vault` is open in an external editor.
+pub fn is_open(state: &AppState, vault: &Vault, name: &str) -> bool {
+    state
+        .external_edits
+        .lock()
+        .unwrap()
+        .contains(&(vault.name.clone(), name.to_string()))
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use common::links::{self, WikiLink};
+
+use crate::store::NoteStore;
+
+/// A note with the references parsed out of it.
+pub struct Note {
+    pub name: String,
+    pub content: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub links: Vec<WikiLink>,
+}
+
+impl Note {
+    pub fn new(name: String, content: String) -> Self {
+        let title = links::title(&name, &content);
+        let mut tags = links::tags(&content)
+            .into_iter()
+            .map(|t| t.name)
+            .collect::<Vec<_>>();
+        tags.sort();
+        tags.dedup();
+        let links = links::wiki_links(&content);
+        Note {
+            name,
+            content,
+            title,
+            tags,
+            links,
+        }
+    }
+}
+
+/// Link and tag index over every note in a directory.
+pub struct Index {
+    pub notes: Vec<Note>,
+}
+
+impl Index {
+    pub fn scan(store: &dyn NoteStore) -> std::io::Result<Self> {
+        let mut notes = Vec::new();
+        for name in note_names(store)? {
+            match store.read_to_string(&name) {
+                Ok(content) => notes.push(Note::new(name, content)),
+                Err(e) => eprintln!("skip {}: {}", name, e),
+            }
+        }
+        notes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Index { notes })
+    }
+
+    /// Replaces the note named `name` by one with `content`, adding it if it
+    /// is new.
+    pub fn update(&mut self, name: &str, content: String) {
+        let note = Note::new(name.to_string(), content);
+        match self.notes.binary_search_by(|n| n.name.as_str().cmp(name)) {
+            Ok(i) => self.notes[i] = note,
+            Err(i) => self.notes.insert(i, note),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.notes.retain(|n| n.name != name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Note> {
+        self.notes.iter().find(|n| n.name == name)
+    }
+
+    pub fn resolve(&self, target: &str) -> Option<&Note> {
+        links::resolve(target, self.notes.iter().map(|n| n.name.as_str()))
+            .and_then(|name| self.get(name))
+    }
+
+    /// The notes that link to each note, by the name of the linked note.
+    /// Each link target is resolved once, however often it appears.
+    pub fn backlinks(&self) -> HashMap<&str, Vec<&Note>> {
+        let mut resolved: HashMap<&str, Option<&str>> = HashMap::new();
+        let mut backlinks: HashMap<&str, Vec<&Note>> = HashMap::new();
+        for note in &self.notes {
+            for link in &note.links {
+                let target = *resolved.entry(link.target.as_str()).or_insert_with(|| {
+                    links::resolve(&link.target, self.notes.iter().map(|n| n.name.as_str()))
+                });
+                let target = match target {
+                    Some(t) if t != note.name => t,
+                    _ => continue,
+                };
+                let sources = backlinks.entry(target).or_default();
+                if sources.last().map(|s| s.name != note.name).unwrap_or(true) {
+                    sources.push(note);
+                }
+            }
+        }
+        backlinks
+    }
+}
+
+/// Names of all notes in `store`. Hidden files and directories are skipped,
+/// as are files that don't look like notes.
+pub fn note_names(store: &dyn NoteStore) -> std::io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    collect_note_names(store, "", &mut names)?;
+    Ok(names)
+}
+
+fn collect_note_names(
+    store: &dyn NoteStore,
+    directory: &str,
+    names: &mut Vec<String>,
+) -> std::io::Result<()> {
+    for entry in store.list(directory)? {
+        let file_name = entry.name.rsplit('/').next().unwrap_or("");
+        if file_name.starts_with('.') {
+            continue;
+        }
+        if entry.is_directory {
+            collect_note_names(store, &entry.name, names)?;
+        } else if is_note(file_name) {
+            names.push(entry.name);
+        }
+    }
+    Ok(())
+}
+
+pub fn is_note(file_name: &str) -> bool {
+    match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some(extension) => ["md", "markdown", "txt"].contains(&extension.to_lowercase().as_str()),
+        None => true,
+    }
+}
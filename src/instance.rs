@@ -0,0 +1,184 @@
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{BufRead, BufReader, Error, ErrorKind, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::config::{create_private_directory, runtime_directory};
+
+const SOCKET_FILE: &str = "instance.sock";
+const LOCK_FILE: &str = "instance.lock";
+
+/// How long a later launch waits for the running instance to handle its
+/// arguments.
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often and how long apart a launch tries to reach the running instance
+/// while that one is still starting up.
+const CONNECT_ATTEMPTS: u32 = 40;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The running instance of the `webview` binary, found through a Unix socket
+/// in the [`runtime_directory`]. A second launch hands its arguments to the
+/// first instead of starting a second server whose saves would race with it.
+pub enum Instance {
+    /// No other instance is running; later launches connect to the listener.
+    Primary(Listener),
+    /// Connected to the instance that is running.
+    Secondary(UnixStream),
+}
+
+/// The socket of the primary instance. The lock that makes the instance the
+/// primary one is held as long as the listener is.
+pub struct Listener {
+    listener: UnixListener,
+    lock: File,
+}
+
+impl Instance {
+    pub async fn claim() -> std::io::Result<Self> {
+        tokio::task::spawn_blocking(Self::claim_blocking)
+            .await
+            .expect("claim instance")
+    }
+
+    fn claim_blocking() -> std::io::Result<Self> {
+        let directory = runtime_directory();
+        create_private_directory(&directory)?;
+        let lock = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(directory.join(LOCK_FILE))?;
+        let path = directory.join(SOCKET_FILE);
+        for _ in 0..CONNECT_ATTEMPTS {
+            // Whoever holds the lock is the primary instance, so a socket
+            // found while holding it was left behind by one that crashed.
+            match lock.try_lock() {
+                Ok(()) => {
+                    match std::fs::remove_file(&path) {
+                        Err(e) if e.kind() != ErrorKind::NotFound => return Err(e),
+                        _ => {}
+                    }
+                    let listener = UnixListener::bind(&path)?;
+                    return Ok(Instance::Primary(Listener { listener, lock }));
+                }
+                Err(TryLockError::WouldBlock) => {}
+                Err(TryLockError::Error(e)) => return Err(e),
+            }
+            // The primary instance may not be listening yet.
+            match UnixStream::connect(&path) {
+                Ok(s) => return Ok(Instance::Secondary(s)),
+                Err(_) => std::thread::sleep(CONNECT_INTERVAL),
+            }
+        }
+        Err(Error::new(
+            ErrorKind::TimedOut,
+            "the running instance does not accept connections",
+        ))
+    }
+}
+
+/// Sends `args` to the running instance and waits until it has handled
+/// them. The inner result is the outcome reported by that instance.
+pub async fn forward(stream: UnixStream, args: Vec<String>) -> std::io::Result<Result<(), String>> {
+    tokio::task::spawn_blocking(move || forward_blocking(stream, &args))
+        .await
+        .expect("forward arguments")
+}
+
+fn forward_blocking(
+    mut stream: UnixStream,
+    args: &[String],
+) -> std::io::Result<Result<(), String>> {
+    // The running instance may hang, which must not hang this launch too.
+    stream.set_read_timeout(Some(FORWARD_TIMEOUT))?;
+    stream.set_write_timeout(Some(FORWARD_TIMEOUT))?;
+    let mut line = serde_json::to_string(args).expect("serialize arguments");
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    let mut reply = String::new();
+    BufReader::new(stream)
+        .read_line(&mut reply)
+        .map_err(|e| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => {
+                Error::new(ErrorKind::TimedOut, "the running instance did not answer")
+            }
+            _ => e,
+        })?;
+    serde_json::from_str(&reply).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Arguments forwarded by a later launch.
+pub struct Forwarded {
+    pub args: Vec<String>,
+    reply: oneshot::Sender<Result<(), String>>,
+}
+
+impl Forwarded {
+    /// Reports the outcome to the launch that sent the arguments.
+    pub fn reply(self, result: Result<(), String>) {
+        // The launch may have given up waiting.
+        let _ = self.reply.send(result);
+    }
+}
+
+/// Accepts later launches on `listener` and yields their arguments.
+pub fn listen(listener: Listener) -> std::io::Result<mpsc::UnboundedReceiver<Forwarded>> {
+    let Listener { listener, lock } = listener;
+    listener.set_nonblocking(true)?;
+    let mut listener = tokio::net::UnixListener::from_std(listener)?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        // Released when the process exits.
+        let _lock = lock;
+        loop {
+            let stream = match listener.accept().await {
+                Ok((s, _)) => s,
+                Err(e) => {
+                    eprintln!("accept failed: {}", e);
+                    continue;
+                }
+            };
+            let sender = sender.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle(stream, sender).await {
+                    println!("failed to read forwarded arguments: {}", e);
+                }
+            });
+        }
+    });
+    Ok(receiver)
+}
+
+async fn handle(
+    stream: tokio::net::UnixStream,
+    sender: mpsc::UnboundedSender<Forwarded>,
+) -> std::io::Result<()> {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut line = String::new();
+    tokio::io::BufReader::new(read).read_line(&mut line).await?;
+    let args = serde_json::from_str(&line).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let (reply, result) = oneshot::channel();
+    if sender.send(Forwarded { args, reply }).is_err() {
+        return Ok(());
+    }
+    let result = result
+        .await
+        .unwrap_or_else(|_| Err("the window was closed".to_string()));
+    let mut line = serde_json::to_string(&result).expect("serialize reply");
+    line.push('\n');
+    write.write_all(line.as_bytes()).await
+}
+
+/// Removes the socket once the instance exits, so that the next launch
+/// starts a new one.
+pub fn release() {
+    let _ = std::fs::remove_file(socket_path());
+}
+
+fn socket_path() -> PathBuf {
+    runtime_directory().join(SOCKET_FILE)
+}
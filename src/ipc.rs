@@ -0,0 +1,145 @@
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use common::api::Endpoint;
+
+use crate::api_handler::handle_api_request;
+use crate::AppState;
+
+/// An API request sent by the frontend through the webview's native bridge.
+/// Bodies are base64 encoded so that binary uploads survive the JSON bridge.
+#[derive(Deserialize)]
+struct IpcRequest {
+    method: String,
+    /// Path and query of the request, e.g. `/api/files?key=foo`.
+    path: String,
+    content_type: Option<String>,
+    #[serde(default)]
+    body: String,
+}
+
+#[derive(Serialize)]
+struct IpcResponse {
+    status: u16,
+    content_type: Option<String>,
+    body: String,
+}
+
+/// Serves the API to the webview without going through the HTTP server. The
+/// frontend's fetch shim hands requests to the binding registered in
+/// `main.rs`, which passes them on to [`Bridge::call`].
+#[derive(Clone)]
+pub struct Bridge {
+    state: Arc<AppState>,
+}
+
+impl Bridge {
+    pub(crate) fn new(state: Arc<AppState>) -> Self {
+        Bridge { state }
+    }
+
+    /// Handles the arguments of a bridge call, a JSON array holding one
+    /// request, and returns the response as a JSON object.
+    pub async fn call(&self, args: &str) -> String {
+        let response = match serde_json::from_str::<(IpcRequest,)>(args) {
+            Ok((request,)) => self.handle(request).await,
+            Err(e) => {
+                println!("invalid ipc request: {}", e);
+                IpcResponse {
+                    status: 400,
+                    content_type: None,
+                    body: String::new(),
+                }
+            }
+        };
+        serde_json::to_string(&response).expect("serialize IpcResponse")
+    }
+
+    async fn handle(&self, request: IpcRequest) -> IpcResponse {
+        let error = |status| IpcResponse {
+            status,
+            content_type: None,
+            body: String::new(),
+        };
+        if !request.path.starts_with("/api/") || request.path == common::api::EVENTS_PATH {
+            return error(404);
+        }
+        if self.state.is_shutting_down() {
+            return error(503);
+        }
+        let body = match base64::decode(&request.body) {
+            Ok(b) => b,
+            Err(_) => return error(400),
+        };
+        let mut builder = Request::builder()
+            .method(request.method.as_str())
+            .uri(request.path.as_str());
+        if let Some(content_type) = &request.content_type {
+            builder = builder.header(CONTENT_TYPE, content_type.as_str());
+        }
+        let req = match builder.body(Body::from(body)) {
+            Ok(r) => r,
+            Err(_) => return error(400),
+        };
+        let response = match handle_api_request(req, self.state.clone()).await {
+            Ok(r) => r,
+            Err(e) => {
+                println!("ipc request failed: {}", e);
+                return error(500);
+            }
+        };
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        match hyper::body::to_bytes(response.into_body()).await {
+            Ok(body) => IpcResponse {
+                status,
+                content_type,
+                body: base64::encode(&body),
+            },
+            Err(_) => error(500),
+        }
+    }
+
+    /// Calls `endpoint` the way the frontend would, e.g. for arguments
+    /// forwarded by a later launch of the window.
+    pub async fn request<E: Endpoint>(&self, endpoint: E) -> Result<E::Response, String> {
+        let mut builder = Request::builder()
+            .method(E::METHOD.as_str())
+            .uri(endpoint.path());
+        let body = match endpoint.body() {
+            Some(body) => {
+                builder = builder.header(CONTENT_TYPE, body.content_type);
+                Body::from(body.data)
+            }
+            None => Body::empty(),
+        };
+        let req = builder.body(body).map_err(|e| e.to_string())?;
+        let response = handle_api_request(req, self.state.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        if !response.status().is_success() {
+            return Err(format!("server responded with {}", response.status()));
+        }
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| e.to_string())?;
+        serde_json::from_slice(&body).map_err(|e| e.to_string())
+    }
+
+    /// Access token of the server. The window passes it when loading the
+    /// frontend so that attachments can be fetched over HTTP.
+    pub fn token(&self) -> &str {
+        &self.state.token
+    }
+
+    /// Changes to files in the notes directory, to be pushed to the frontend.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<common::FileEvent> {
+        self.state.events.subscribe()
+    }
+}
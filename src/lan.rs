@@ -0,0 +1,141 @@
+use bytes::buf::ext::BufExt;
+use hyper::header::{CONTENT_TYPE, COOKIE, HOST, LOCATION, ORIGIN, SET_COOKIE};
+use hyper::{Body, Error, Method, Request, Response};
+use percent_encoding::percent_decode_str;
+use std::io::Read;
+use std::time::Duration;
+
+use crate::{auth, AppState};
+
+pub const LOGIN_PATH: &str = "/login";
+const SESSION_COOKIE: &str = "scrapnote_session";
+/// Slows down password guessing.
+const FAILED_LOGIN_DELAY: Duration = Duration::from_secs(1);
+
+const LOGIN_PAGE: &str = "<!doctype html>
+<html lang=\"en\">
+  <head>
+    <meta charset=\"utf-8\">
+    <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">
+    <title>scrapnote</title>
+    <style>
+      body { font-family: \"Roboto\", \"Noto Sans JP\", sans-serif; margin: 0; }
+      form { max-width: 320px; margin: 20vh auto 0 auto; padding: 0 16px; }
+      input { box-sizing: border-box; width: 100%; margin: 8px 0; padding: 8px; font-size: 12pt; }
+      .error { color: firebrick; font-size: 10pt; }
+    </style>
+  </head>
+  <body>
+    <form method=\"post\" action=\"/login\">
+      {error}
+      <input type=\"password\" name=\"password\" placeholder=\"Password\" autofocus>
+      <input type=\"submit\" value=\"Log in\">
+    </form>
+  </body>
+</html>
+";
+
+/// Whether a request made by a web page comes from the page the server
+/// serves. LAN clients reach the server under names that are not known in
+/// advance, so unlike `auth::is_allowed_origin` the host is not checked.
+pub fn is_same_origin(req: &Request<Body>) -> bool {
+    let host = req.headers().get(HOST).and_then(|v| v.to_str().ok());
+    match (req.headers().get(ORIGIN), host) {
+        (Some(origin), Some(host)) => origin.to_str().ok() == Some(&format!("https://{}", host)),
+        (Some(_), None) => false,
+        (None, _) => true,
+    }
+}
+
+pub fn has_session(req: &Request<Body>, state: &AppState) -> bool {
+    let session = req
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|c| c.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value);
+    match session {
+        Some(s) => state.sessions.lock().unwrap().contains(s),
+        None => false,
+    }
+}
+
+/// Response to requests without a session: the frontend is redirected to the
+/// login page, API calls are refused.
+pub fn unauthorized(req: &Request<Body>) -> Response<Body> {
+    if req.uri().path().starts_with("/api") {
+        Response::builder().status(401).body(Body::empty()).unwrap()
+    } else {
+        Response::builder()
+            .status(303)
+            .header(LOCATION, LOGIN_PATH)
+            .body(Body::empty())
+            .unwrap()
+    }
+}
+
+pub async fn handle_login(req: Request<Body>, state: &AppState) -> Result<Response<Body>, Error> {
+    match *req.method() {
+        Method::GET => Ok(login_page(200, "")),
+        Method::POST => {
+            let body = hyper::body::aggregate(req).await?;
+            let mut form = String::new();
+            if body.reader().read_to_string(&mut form).is_err() {
+                return Ok(login_page(400, "Invalid request."));
+            }
+            let password = form_value(&form, "password").unwrap_or_default();
+            let expected = match &state.config.lan {
+                Some(lan) => &lan.password,
+                None => return Ok(login_page(404, "")),
+            };
+            if !auth::equals(&password, expected) {
+                println!("failed login attempt");
+                tokio::time::delay_for(FAILED_LOGIN_DELAY).await;
+                return Ok(login_page(401, "Wrong password."));
+            }
+            let session = auth::generate_token();
+            state.sessions.lock().unwrap().insert(session.clone());
+            Ok(Response::builder()
+                .status(303)
+                .header(LOCATION, "/")
+                .header(
+                    SET_COOKIE,
+                    format!(
+                        "{}={}; Path=/; HttpOnly; Secure; SameSite=Strict",
+                        SESSION_COOKIE, session
+                    ),
+                )
+                .body(Body::empty())
+                .unwrap())
+        }
+        _ => Ok(Response::builder().status(405).body(Body::empty()).unwrap()),
+    }
+}
+
+fn login_page(status: u16, error: &str) -> Response<Body> {
+    let error = if error.is_empty() {
+        String::new()
+    } else {
+        format!("<p class=\"error\">{}</p>", error)
+    };
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(LOGIN_PAGE.replace("{error}", &error)))
+        .unwrap()
+}
+
+/// Value of `name` in an `application/x-www-form-urlencoded` body.
+fn form_value(form: &str, name: &str) -> Option<String> {
+    form.split('&')
+        .filter_map(|p| p.split_once('='))
+        .find(|(n, _)| *n == name)
+        .map(|(_, v)| {
+            percent_decode_str(&v.replace('+', " "))
+                .decode_utf8_lossy()
+                .to_string()
+        })
+}
@@ -1,24 +1,171 @@
+use hyper::header::{
+    ACCEPT_ENCODING, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE, ETAG, IF_NONE_MATCH,
+    SET_COOKIE, VARY,
+};
 use hyper::{Body, Error, Method, Request, Response, Server};
 use std::borrow::Cow;
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::RecvError;
+use tokio::sync::{broadcast, watch, Notify};
+use tokio::task::JoinHandle;
 
 use rust_embed::RustEmbed;
 
 mod api_handler;
 use api_handler::handle_api_request;
 
+mod asset;
+
+mod attachment;
+
+pub mod auth;
+
+mod capture;
+
+mod client;
+pub use client::Client;
+
 mod config;
-pub use config::Config;
+pub use config::{
+    create_private_directory, runtime_directory, save_window_size, saved_window_size,
+    write_private_file, Config, LanConfig, VaultConfig, WindowConfig,
+};
+
+mod external;
+
+mod export;
+pub use export::{export_html, ExportOptions};
+
+mod index;
+
+#[cfg(unix)]
+pub mod instance;
+
+mod ipc;
+pub use ipc::Bridge;
+
+mod lan;
+
+mod lsp;
+pub use lsp::run_language_server;
+
+mod router;
+
+pub mod scratch;
+
+mod store;
+pub use store::{Entry, FsStore, InMemoryStore, Metadata, NoteStore};
+
+mod tls;
+
+mod watcher;
 
 #[derive(RustEmbed)]
 #[folder = "static"]
 struct Asset;
 
+/// A notebook the server keeps notes in.
+#[derive(Clone)]
+struct Vault {
+    name: String,
+    store: Arc<dyn NoteStore>,
+}
+
+struct AppState {
+    config: Config,
+    /// The default vault first, then those in `config.vaults`.
+    vaults: Vec<Vault>,
+    /// Serializes writes to notes so that captures and saves from the editor
+    /// do not interleave. Taken through [`AppState::lock_writes`].
+    write_lock: tokio::sync::Mutex<()>,
+    /// Writes waiting for or holding `write_lock`.
+    pending_writes: AtomicUsize,
+    /// Notified whenever a write finishes.
+    write_done: Notify,
+    /// Changes to files in all vaults, streamed to `/api/events`.
+    events: broadcast::Sender<common::FileEvent>,
+    /// Entity tags of the embedded assets by path, computed on first use.
+    asset_etags: Mutex<HashMap<String, String>>,
+    /// Compressed embedded assets by entity tag and encoding.
+    compressed_assets: Mutex<HashMap<(String, asset::Encoding), bytes::Bytes>>,
+    /// Whether the API is served over HTTP. Otherwise only the frontend and
+    /// attachments are, and the API is reached through an [`ipc::Bridge`].
+    http_api: bool,
+    /// Secret required on every `/api` request over HTTP.
+    token: String,
+    /// Sessions of clients logged in over the LAN.
+    sessions: Mutex<HashSet<String>>,
+    /// Notes that are open in an external editor, by vault and name.
+    external_edits: Mutex<HashSet<(String, String)>>,
+    /// Becomes `true` once the server is asked to shut down.
+    shutdown: watch::Receiver<bool>,
+}
+
+impl AppState {
+    fn is_shutting_down(&self) -> bool {
+        *self.shutdown.borrow()
+    }
+
+    fn vault(&self, name: &str) -> Option<&Vault> {
+        self.vaults.iter().find(|v| v.name == name)
+    }
+
+    /// Takes the write lock, counting the write as pending until the guard
+    /// is dropped.
+    async fn lock_writes(&self) -> WriteGuard<'_> {
+        self.pending_writes.fetch_add(1, Ordering::SeqCst);
+        WriteGuard {
+            _lock: self.write_lock.lock().await,
+            state: self,
+        }
+    }
+
+    /// Waits until no write is pending.
+    async fn writes_done(&self) {
+        while self.pending_writes.load(Ordering::SeqCst) > 0 {
+            // A write finishing between the check and here leaves a permit,
+            // so the wakeup isn't lost.
+            self.write_done.notified().await;
+        }
+    }
+}
+
+struct WriteGuard<'a> {
+    _lock: tokio::sync::MutexGuard<'a, ()>,
+    state: &'a AppState,
+}
+
+impl Drop for WriteGuard<'_> {
+    fn drop(&mut self) {
+        self.state.pending_writes.fetch_sub(1, Ordering::SeqCst);
+        self.state.write_done.notify();
+    }
+}
+
+/// Resolves once `flag` becomes `true`.
+async fn wait_for(mut flag: watch::Receiver<bool>) {
+    while let Some(value) = flag.recv().await {
+        if value {
+            return;
+        }
+    }
+}
+
 fn build_404_response() -> Response<Body> {
     return Response::builder().status(404).body(Body::empty()).unwrap();
 }
 
-async fn handle_static(req: Request<Body>) -> Result<Response<Body>, Error> {
+fn build_status_response(status: u16) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn handle_static(req: Request<Body>, state: Arc<AppState>) -> Result<Response<Body>, Error> {
     let real_path = match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => "index.html",
         (&Method::GET, path) => &path[1..],
@@ -26,47 +173,425 @@ async fn handle_static(req: Request<Body>) -> Result<Response<Body>, Error> {
             return Ok(build_404_response());
         }
     };
-    match Asset::get(real_path) {
-        Some(content) => {
-            let body: Body = match content {
-                Cow::Borrowed(bytes) => bytes.into(),
-                Cow::Owned(bytes) => bytes.into(),
+    let content = match Asset::get(real_path) {
+        Some(c) => c,
+        None => return Ok(build_404_response()),
+    };
+    let cached = state.asset_etags.lock().unwrap().get(real_path).cloned();
+    let etag = match cached {
+        Some(e) => e,
+        None => {
+            let etag = asset::etag(&content);
+            state
+                .asset_etags
+                .lock()
+                .unwrap()
+                .insert(real_path.to_string(), etag.clone());
+            etag
+        }
+    };
+    let content_type = asset::content_type(real_path);
+    let mut builder = Response::builder()
+        .header(ETAG, &etag)
+        .header(CACHE_CONTROL, "no-cache");
+    if let Some(token) = auth::query_token(&req) {
+        if auth::equals(token, &state.token) {
+            builder = builder.header(SET_COOKIE, auth::token_cookie(token));
+        }
+    }
+    let not_modified = req
+        .headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| asset::etag_matches(v, &etag))
+        .unwrap_or(false);
+    if not_modified {
+        return Ok(builder.status(304).body(Body::empty()).unwrap());
+    }
+    let builder = builder.header(CONTENT_TYPE, &content_type);
+    if !asset::is_compressible(&content_type, content.len()) {
+        let body: Body = match content {
+            Cow::Borrowed(bytes) => bytes.into(),
+            Cow::Owned(bytes) => bytes.into(),
+        };
+        return Ok(builder.status(200).body(body).unwrap());
+    }
+    let builder = builder.header(VARY, "Accept-Encoding");
+    let encoding = req
+        .headers()
+        .get(ACCEPT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(asset::negotiate);
+    let response = match encoding {
+        Some(encoding) => {
+            let key = (etag, encoding);
+            let cached = state.compressed_assets.lock().unwrap().get(&key).cloned();
+            let body = match cached {
+                Some(b) => b,
+                None => {
+                    // Compressing a large asset takes a while, which must
+                    // neither hold up other requests nor the cache.
+                    let content = content.into_owned();
+                    let compressed: bytes::Bytes =
+                        tokio::task::spawn_blocking(move || asset::compress(&content, encoding))
+                            .await
+                            .expect("compress asset")
+                            .into();
+                    state
+                        .compressed_assets
+                        .lock()
+                        .unwrap()
+                        .insert(key, compressed.clone());
+                    compressed
+                }
             };
-            Ok(Response::builder().status(200).body(body).unwrap())
+            builder
+                .status(200)
+                .header(CONTENT_ENCODING, encoding.name())
+                .body(Body::from(body))
         }
-        None => Ok(build_404_response()),
+        None => builder.status(200).body(Body::from(content.into_owned())),
+    };
+    Ok(response.unwrap())
+}
+
+/// A running server, returned by [`start_server`] and
+/// [`start_webview_server`].
+pub struct ServerHandle {
+    addr: SocketAddr,
+    state: Arc<AppState>,
+    ready: watch::Receiver<bool>,
+    shutdown: watch::Sender<bool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Address the local listener is bound to.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Resolves once the server accepts connections, including on the LAN
+    /// address if one is configured. Resolves to `false` if the LAN listener
+    /// failed to start.
+    pub async fn ready(&self) -> bool {
+        wait_for(self.ready.clone()).await;
+        *self.ready.borrow()
+    }
+
+    /// Stops accepting connections and waits until the requests in flight
+    /// are answered and pending saves are written. Event streams are closed.
+    pub async fn shutdown(self) {
+        let _ = self.shutdown.broadcast(true);
+        self.wait().await
     }
+
+    /// Waits until the server stops.
+    pub async fn wait(self) {
+        for task in self.tasks {
+            if let Err(e) = task.await {
+                eprintln!("{}", e);
+            }
+        }
+        // Saves made through the bridge are not tracked by hyper.
+        self.state.writes_done().await;
+        auth::remove_token_file(self.addr.port());
+        println!("Server stopped");
+    }
+}
+
+pub fn start_server(port: Option<u16>, config: Config) -> ServerHandle {
+    let store = Arc::new(FsStore::new(&config.file_directory));
+    serve(port, config, store, true)
 }
 
-pub fn start_server(port: Option<u16>, config: Config) -> (u16, tokio::task::JoinHandle<()>) {
+/// Like [`start_server`], but keeps the notes of the default vault in
+/// `store` instead of `config.file_directory`.
+pub fn start_server_with_store(
+    port: Option<u16>,
+    config: Config,
+    store: Arc<dyn NoteStore>,
+) -> ServerHandle {
+    serve(port, config, store, true)
+}
+
+/// Starts the server for the webview window, which talks to the API through
+/// the returned bridge. The API is also served over HTTP only if `http_api`
+/// is set, e.g. to open the app in a browser next to the window.
+pub fn start_webview_server(
+    port: Option<u16>,
+    config: Config,
+    http_api: bool,
+) -> (ServerHandle, Bridge) {
+    let store = Arc::new(FsStore::new(&config.file_directory));
+    let handle = serve(port, config, store, http_api);
+    let bridge = Bridge::new(handle.state.clone());
+    (handle, bridge)
+}
+
+fn serve(
+    port: Option<u16>,
+    config: Config,
+    store: Arc<dyn NoteStore>,
+    http_api: bool,
+) -> ServerHandle {
     let port = match port {
         Some(p) => p,
         None => 0,
     };
+    let mut vaults = vec![Vault {
+        name: common::DEFAULT_VAULT.to_string(),
+        store,
+    }];
+    for vault in &config.vaults {
+        vaults.push(Vault {
+            name: vault.name.clone(),
+            store: Arc::new(FsStore::new(&vault.directory)),
+        });
+    }
+    for vault in &vaults {
+        scratch::cleanup(vault.store.as_ref(), &config);
+    }
+    let (events, _) = broadcast::channel(64);
+    let (shutdown_sender, shutdown) = watch::channel(false);
+    for vault in &vaults {
+        watch_vault(vault, events.clone(), shutdown.clone());
+    }
     let addr = ([127, 0, 0, 1], port).into();
+    let state = Arc::new(AppState {
+        config,
+        vaults,
+        write_lock: tokio::sync::Mutex::new(()),
+        pending_writes: AtomicUsize::new(0),
+        write_done: Notify::new(),
+        events,
+        asset_etags: Mutex::new(HashMap::new()),
+        compressed_assets: Mutex::new(HashMap::new()),
+        http_api,
+        token: auth::generate_token(),
+        sessions: Mutex::new(HashSet::new()),
+        external_edits: Mutex::new(HashSet::new()),
+        shutdown: shutdown.clone(),
+    });
     let server = Server::bind(&addr).serve(MakeService {
-        config: Arc::new(config),
+        state: state.clone(),
+        lan: false,
     });
-    let port = server.local_addr().port();
-    println!("Server started on http://127.0.0.1:{}", port);
-    (
-        port,
-        tokio::spawn(async move {
-            if let Err(e) = server.await {
-                eprintln!("{}", e);
+    let addr = server.local_addr();
+    // The token file points the command line tool at the server, which is
+    // of no use when the API is only reachable through the bridge.
+    let token_file = if state.http_api {
+        auth::write_token_file(addr.port(), &state.token).map(Some)
+    } else {
+        Ok(None)
+    };
+    match token_file {
+        Ok(Some(path)) => println!(
+            "Server started on http://127.0.0.1:{}/ with the access token in {}",
+            addr.port(),
+            path.display()
+        ),
+        Ok(None) => println!("Server started on http://127.0.0.1:{}/", addr.port()),
+        Err(e) => {
+            println!("Server started on http://127.0.0.1:{}/", addr.port());
+            eprintln!("failed to write access token: {}", e);
+        }
+    }
+    let (ready_sender, ready) = watch::channel(false);
+    let mut tasks = vec![tokio::spawn(async move {
+        if let Err(e) = server.with_graceful_shutdown(wait_for(shutdown)).await {
+            eprintln!("{}", e);
+        }
+    })];
+    match start_lan_server(state.clone()) {
+        Some((task, lan_ready)) => {
+            tasks.push(task);
+            tokio::spawn(async move {
+                // The sender is dropped unsent when binding fails.
+                if lan_ready.await.is_ok() {
+                    let _ = ready_sender.broadcast(true);
+                }
+            });
+        }
+        None => {
+            let _ = ready_sender.broadcast(true);
+        }
+    }
+    ServerHandle {
+        addr,
+        state,
+        ready,
+        shutdown: shutdown_sender,
+        tasks,
+    }
+}
+
+/// Forwards the changes in the store of `vault` to `events`, tagged with the
+/// name of the vault.
+fn watch_vault(
+    vault: &Vault,
+    events: broadcast::Sender<common::FileEvent>,
+    shutdown: watch::Receiver<bool>,
+) {
+    let (sender, mut received) = broadcast::channel(64);
+    vault.store.watch(sender);
+    let name = vault.name.clone();
+    tokio::spawn(async move {
+        let shutdown = wait_for(shutdown);
+        tokio::pin!(shutdown);
+        loop {
+            let received = tokio::select! {
+                received = received.recv() => received,
+                _ = &mut shutdown => break,
+            };
+            let mut event = match received {
+                Ok(e) => e,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            event.vault = name.clone();
+            // Sending only fails when nobody is subscribed.
+            let _ = events.send(event);
+        }
+    });
+}
+
+/// Serves the app over TLS on the address configured in `config.lan`. The
+/// receiver fires once the listener is bound, and is closed if binding
+/// fails.
+fn start_lan_server(
+    state: Arc<AppState>,
+) -> Option<(JoinHandle<()>, tokio::sync::oneshot::Receiver<()>)> {
+    let lan = state.config.lan.as_ref()?;
+    let address: std::net::SocketAddr = match lan.address.parse() {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("invalid LAN address {}: {}", lan.address, e);
+            return None;
+        }
+    };
+    let tls_config = match tls::server_config(lan) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("failed to set up TLS: {}", e);
+            return None;
+        }
+    };
+    let (ready, ready_receiver) = tokio::sync::oneshot::channel();
+    let task = tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(l) => l,
+            Err(e) => {
+                eprintln!("failed to listen on {}: {}", address, e);
+                return;
             }
-        }),
-    )
+        };
+        let _ = ready.send(());
+        println!("LAN access on https://{}", address);
+        let incoming = tls::incoming(listener, tls_config, wait_for(state.shutdown.clone()));
+        let shutdown = wait_for(state.shutdown.clone());
+        let server = Server::builder(hyper::server::accept::from_stream(incoming))
+            .serve(MakeService { state, lan: true })
+            .with_graceful_shutdown(shutdown);
+        if let Err(e) = server.await {
+            eprintln!("{}", e);
+        }
+    });
+    Some((task, ready_receiver))
+}
+
+async fn handle_lan_request(
+    req: Request<Body>,
+    state: Arc<AppState>,
+) -> Result<Response<Body>, Error> {
+    if !lan::is_same_origin(&req) {
+        return Ok(build_status_response(403));
+    }
+    if req.uri().path() == lan::LOGIN_PATH {
+        return lan::handle_login(req, &state).await;
+    }
+    if !lan::has_session(&req, &state) {
+        return Ok(lan::unauthorized(&req));
+    }
+    if api_handler::is_local_only(&req) {
+        // It would open the editor on this machine rather than the client's.
+        return Ok(build_status_response(403));
+    }
+    if req.uri().path().starts_with("/api") {
+        handle_api_request(req, state).await
+    } else {
+        handle_static(req, state).await
+    }
+}
+
+/// The vaults given as `--vault NAME=DIRECTORY`, which may be repeated.
+/// Exits on a malformed one or a name given twice.
+pub fn vault_args(args: &[String]) -> Vec<VaultConfig> {
+    let mut vaults: Vec<VaultConfig> = Vec::new();
+    let values = args.iter().zip(args.iter().skip(1));
+    for (_, value) in values.filter(|(a, _)| *a == "--vault") {
+        let vault = match VaultConfig::parse(value) {
+            Some(v) => v,
+            None => {
+                eprintln!("invalid --vault {}, expected NAME=DIRECTORY", value);
+                std::process::exit(2);
+            }
+        };
+        if vaults.iter().any(|v| v.name == vault.name) {
+            eprintln!("vault {} is given more than once", vault.name);
+            std::process::exit(2);
+        }
+        vaults.push(vault);
+    }
+    vaults
 }
 
 #[tokio::main]
 #[allow(unused_must_use)]
 async fn main() {
-    start_server(Some(8080), Config::default()).1.await;
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    let option = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    let mut config = Config::default();
+    // `--editor COMMAND` overrides `$EDITOR` for opening notes externally.
+    config.external_editor = option("--editor");
+    // `--export-dir DIR` lets `/api/export` write the site to DIR.
+    config.export_directory = option("--export-dir");
+    config.vaults = vault_args(&args);
+    // `--lan ADDRESS [--cert PEM --key PEM]` additionally serves the app to
+    // other machines. The login password is taken from the environment so
+    // that it doesn't show up in the process list.
+    if let Some(address) = option("--lan") {
+        let password = match std::env::var("SCRAPNOTE_PASSWORD") {
+            Ok(p) if !p.is_empty() => p,
+            _ => {
+                eprintln!("--lan requires a password in SCRAPNOTE_PASSWORD");
+                std::process::exit(2);
+            }
+        };
+        config.lan = Some(LanConfig {
+            address,
+            password,
+            certificate: option("--cert"),
+            private_key: option("--key"),
+        });
+    }
+    let server = start_server(Some(8080), config);
+    // Let pending saves finish when stopped with Ctrl+C.
+    match tokio::signal::ctrl_c().await {
+        Ok(()) => server.shutdown().await,
+        Err(_) => server.wait().await,
+    }
 }
 
 struct Service {
-    config: Arc<Config>,
+    state: Arc<AppState>,
+    /// Whether the connection came in over the LAN listener.
+    lan: bool,
 }
 use std::future::Future;
 use std::pin::Pin;
@@ -85,17 +610,35 @@ impl hyper::service::Service<Request<Body>> for Service {
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
-        let config = self.config.clone();
-        if req.uri().path().starts_with("/api") {
-            Box::pin(handle_api_request(req, config))
+        let state = self.state.clone();
+        if self.lan {
+            return Box::pin(handle_lan_request(req, state));
+        }
+        if !auth::is_allowed_origin(&req) {
+            println!(
+                "rejected request from foreign host or origin: {}",
+                req.uri()
+            );
+            return Box::pin(async { Ok(build_status_response(403)) });
+        }
+        let path = req.uri().path();
+        if path.starts_with("/api") {
+            if !state.http_api && !attachment::is_url_path(path) {
+                return Box::pin(async { Ok(build_404_response()) });
+            }
+            if !auth::has_token(&req, &state.token) {
+                return Box::pin(async { Ok(build_status_response(401)) });
+            }
+            Box::pin(handle_api_request(req, state))
         } else {
-            Box::pin(handle_static(req))
+            Box::pin(handle_static(req, state))
         }
     }
 }
 
 struct MakeService {
-    config: Arc<Config>,
+    state: Arc<AppState>,
+    lan: bool,
 }
 
 impl<T> hyper::service::Service<T> for MakeService {
@@ -108,8 +651,9 @@ impl<T> hyper::service::Service<T> for MakeService {
     }
 
     fn call(&mut self, _req: T) -> Self::Future {
-        let config = self.config.clone();
-        let fut = async move { Ok(Service { config }) };
+        let state = self.state.clone();
+        let lan = self.lan;
+        let fut = async move { Ok(Service { state, lan }) };
         Box::pin(fut)
     }
 }
@@ -0,0 +1,607 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io::{BufRead, Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use common::links::{self, WikiLink};
+use common::path;
+
+use crate::config::Config;
+use crate::index::{Index, Note};
+use crate::store::{FsStore, NoteStore};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_NOT_INITIALIZED: i64 = -32002;
+const REQUEST_FAILED: i64 = -32803;
+
+const COMPLETION_KIND_FILE: u32 = 17;
+const COMPLETION_KIND_KEYWORD: u32 = 14;
+const SYMBOL_KIND_STRING: u32 = 15;
+/// `TextDocumentSyncKind.Full`: changes carry the whole document.
+const SYNC_FULL: u32 = 1;
+
+/// Serves the Language Server Protocol on stdin and stdout until the client
+/// exits, so that editors get completion, navigation and renames for wiki
+/// links. Notes are read from `root`, or from the workspace the client opens
+/// when `root` is `None`.
+pub fn run_language_server(root: Option<PathBuf>) -> std::io::Result<()> {
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let stdout = std::io::stdout();
+    let mut output = stdout.lock();
+    let mut server = Server {
+        root,
+        workspace: None,
+    };
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or("");
+        let params = &message["params"];
+        match message.get("id") {
+            Some(id) => {
+                let response = match server.request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err((code, error)) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": error },
+                    }),
+                };
+                write_message(&mut output, &response)?;
+            }
+            None if method == "exit" => break,
+            None => server.notification(method, params),
+        }
+    }
+    Ok(())
+}
+
+/// Reads one message, `None` at the end of the input.
+fn read_message(input: &mut impl BufRead) -> std::io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length =
+        length.ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> std::io::Result<()> {
+    let body = serde_json::to_string(message).expect("serialize message");
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+type Response = Result<Value, (i64, String)>;
+
+struct Server {
+    root: Option<PathBuf>,
+    /// Set up on `initialize`.
+    workspace: Option<Workspace>,
+}
+
+impl Server {
+    fn request(&mut self, method: &str, params: &Value) -> Response {
+        if method == "initialize" {
+            return Ok(self.initialize(params));
+        }
+        if method == "shutdown" {
+            return Ok(Value::Null);
+        }
+        let workspace = match &self.workspace {
+            Some(w) => w,
+            None => return Err((SERVER_NOT_INITIALIZED, "not initialized".to_string())),
+        };
+        match method {
+            "textDocument/completion" => Ok(workspace.completion(params)),
+            "textDocument/definition" => Ok(workspace.definition(params)),
+            "textDocument/references" => Ok(workspace.references(params)),
+            "workspace/symbol" => Ok(workspace.symbols(params)),
+            "textDocument/rename" => workspace.rename(params),
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method {}", method))),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) {
+        let workspace = match &mut self.workspace {
+            Some(w) => w,
+            None => return,
+        };
+        let document = &params["textDocument"];
+        match method {
+            "textDocument/didOpen" => {
+                if let Some(text) = document["text"].as_str() {
+                    workspace.open(document, text.to_string());
+                }
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()?["text"].as_str()) {
+                    workspace.open(document, text.to_string());
+                }
+            }
+            "textDocument/didClose" => workspace.close(document),
+            "textDocument/didSave"
+            | "workspace/didChangeWatchedFiles"
+            | "workspace/didRenameFiles" => workspace.rescan(),
+            _ => {}
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        let root = self
+            .root
+            .clone()
+            .or_else(|| params["rootUri"].as_str().and_then(uri_path))
+            .unwrap_or_else(|| PathBuf::from(Config::default().file_directory));
+        // Clients send absolute URIs, which names are taken relative to.
+        let root = canonical(&root);
+        eprintln!("scrapnote language server for {}", root.display());
+        self.workspace = Some(Workspace::new(root));
+        json!({
+            "capabilities": {
+                "textDocumentSync": { "openClose": true, "change": SYNC_FULL, "save": true },
+                "completionProvider": { "triggerCharacters": ["[", "#"] },
+                "definitionProvider": true,
+                "referencesProvider": true,
+                "workspaceSymbolProvider": true,
+                "renameProvider": true,
+            },
+            "serverInfo": { "name": "scrapnote" },
+        })
+    }
+}
+
+/// The notes below the workspace root, with the content of the documents
+/// open in the client in place of the files.
+struct Workspace {
+    root: PathBuf,
+    store: FsStore,
+    index: Index,
+    open: HashMap<String, String>,
+}
+
+impl Workspace {
+    fn new(root: PathBuf) -> Self {
+        let store = FsStore::new(&root);
+        let index = scan(&store);
+        Workspace {
+            root,
+            store,
+            index,
+            open: HashMap::new(),
+        }
+    }
+
+    fn open(&mut self, document: &Value, text: String) {
+        if let Some(name) = self.name(document) {
+            self.index.update(&name, text.clone());
+            self.open.insert(name, text);
+        }
+    }
+
+    fn close(&mut self, document: &Value) {
+        if let Some(name) = self.name(document) {
+            self.open.remove(&name);
+            match self.store.read_to_string(&name) {
+                Ok(content) => self.index.update(&name, content),
+                Err(_) => self.index.remove(&name),
+            }
+        }
+    }
+
+    fn rescan(&mut self) {
+        self.index = scan(&self.store);
+        for (name, text) in &self.open {
+            self.index.update(name, text.clone());
+        }
+    }
+
+    fn completion(&self, params: &Value) -> Value {
+        let (note, offset) = match self.cursor(params) {
+            Some(c) => c,
+            None => return Value::Null,
+        };
+        let content = &note.content;
+        let line_start = content[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let before = &content[line_start..offset];
+
+        // A wiki link being typed: `[[` without `]]` or `|` after it.
+        if let Some(open) = before.rfind("[[") {
+            let typed = &before[open + 2..];
+            if !typed.contains("]]") && !typed.contains('|') {
+                let start = line_start + open + 2;
+                let close = if content[offset..].starts_with("]]") {
+                    ""
+                } else {
+                    "]]"
+                };
+                let items = self
+                    .index
+                    .notes
+                    .iter()
+                    .map(|n| {
+                        let target = links::stem(&n.name);
+                        json!({
+                            "label": target,
+                            "kind": COMPLETION_KIND_FILE,
+                            "detail": n.title,
+                            "textEdit": {
+                                "range": range(content, start, offset),
+                                "newText": format!("{}{}", target, close),
+                            },
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                return json!(items);
+            }
+        }
+
+        // A tag being typed: `#` at the start of a word.
+        let word_start = before
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+            .map(|(i, c)| i + c.len_utf8())
+            .unwrap_or(0);
+        let word = &before[word_start..];
+        if !word.starts_with('#') || word[1..].contains('#') {
+            return json!([]);
+        }
+        let tags = self
+            .index
+            .notes
+            .iter()
+            .flat_map(|n| n.tags.iter())
+            // The tag being typed is in the index already.
+            .filter(|t| **t != word[1..])
+            .collect::<BTreeSet<_>>();
+        let start = line_start + word_start;
+        let items = tags
+            .into_iter()
+            .map(|tag| {
+                json!({
+                    "label": format!("#{}", tag),
+                    "kind": COMPLETION_KIND_KEYWORD,
+                    "textEdit": {
+                        "range": range(content, start, offset),
+                        "newText": format!("#{}", tag),
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        json!(items)
+    }
+
+    /// Where the link under the cursor points to: the heading it names after
+    /// `#`, or the start of the note.
+    fn definition(&self, params: &Value) -> Value {
+        let (note, offset) = match self.cursor(params) {
+            Some(c) => c,
+            None => return Value::Null,
+        };
+        let link = match link_at(note, offset) {
+            Some(l) => l,
+            None => return Value::Null,
+        };
+        let target = match self.index.resolve(&link.target) {
+            Some(t) => t,
+            None => return Value::Null,
+        };
+        let start = link
+            .target
+            .split_once('#')
+            .and_then(|(_, fragment)| {
+                links::headings(&target.content)
+                    .into_iter()
+                    .find(|h| h.title.eq_ignore_ascii_case(fragment.trim()))
+            })
+            .map(|h| h.start)
+            .unwrap_or(0);
+        self.location(target, start, start)
+    }
+
+    /// Links to the note under the cursor: the target of the link there, or
+    /// the note itself.
+    fn references(&self, params: &Value) -> Value {
+        let (note, offset) = match self.cursor(params) {
+            Some(c) => c,
+            None => return Value::Null,
+        };
+        let target = match link_at(note, offset) {
+            Some(link) => match self.index.resolve(&link.target) {
+                Some(t) => t,
+                None => return json!([]),
+            },
+            None => note,
+        };
+        let mut locations = Vec::new();
+        if params["context"]["includeDeclaration"].as_bool() == Some(true) {
+            locations.push(self.location(target, 0, 0));
+        }
+        for (source, link) in self.links_to(&target.name) {
+            locations.push(self.location(source, link.start, link.end));
+        }
+        json!(locations)
+    }
+
+    /// Headings of all notes whose title contains the query.
+    fn symbols(&self, params: &Value) -> Value {
+        let query = params["query"].as_str().unwrap_or("").to_lowercase();
+        let mut symbols = Vec::new();
+        for note in &self.index.notes {
+            for heading in links::headings(&note.content) {
+                if !heading.title.to_lowercase().contains(&query) {
+                    continue;
+                }
+                let end = note.content[heading.start..]
+                    .find('\n')
+                    .map(|i| heading.start + i)
+                    .unwrap_or(note.content.len());
+                symbols.push(json!({
+                    "name": heading.title,
+                    "kind": SYMBOL_KIND_STRING,
+                    "location": self.location(note, heading.start, end),
+                    "containerName": note.name,
+                }));
+            }
+        }
+        json!(symbols)
+    }
+
+    /// Renames the note under the cursor, or the target of the link there,
+    /// and rewrites the links to it. A new name without `/` keeps the note in
+    /// its directory, one without extension keeps its extension.
+    fn rename(&self, params: &Value) -> Response {
+        let invalid = |message: &str| Err((INVALID_PARAMS, message.to_string()));
+        let (note, offset) = match self.cursor(params) {
+            Some(c) => c,
+            None => return invalid("unknown document"),
+        };
+        let target = match link_at(note, offset) {
+            Some(link) => match self.index.resolve(&link.target) {
+                Some(t) => t,
+                None => return invalid("the link points to no note"),
+            },
+            None => note,
+        };
+        let new_name = match params["newName"].as_str().map(str::trim) {
+            Some(n) if !n.is_empty() => n,
+            _ => return invalid("missing new name"),
+        };
+        let old_name = target.name.as_str();
+        let mut new_name = match (new_name.contains('/'), old_name.rsplit_once('/')) {
+            (false, Some((directory, _))) => format!("{}/{}", directory, new_name),
+            _ => new_name.to_string(),
+        };
+        if links::stem(&new_name) == new_name {
+            new_name.push_str(&old_name[links::stem(old_name).len()..]);
+        }
+        if new_name == old_name {
+            return Ok(Value::Null);
+        }
+        if self.index.get(&new_name).is_some() || self.root.join(&new_name).exists() {
+            return Err((REQUEST_FAILED, format!("{} already exists", new_name)));
+        }
+
+        let mut edits: Vec<(&Note, Value)> = Vec::new();
+        for (source, link) in self.links_to(old_name) {
+            let edit = json!({
+                "range": range(&source.content, link.start, link.end),
+                "newText": rewrite_link(&source.content[link.start..link.end], link, &new_name),
+            });
+            match edits.iter_mut().find(|(n, _)| n.name == source.name) {
+                Some((_, e)) => e.as_array_mut().expect("edits").push(edit),
+                None => edits.push((source, json!([edit]))),
+            }
+        }
+        // Edits refer to the documents by their names before the rename.
+        let mut changes = edits
+            .into_iter()
+            .map(|(source, edits)| {
+                json!({
+                    "textDocument": { "uri": self.uri(&source.name), "version": Value::Null },
+                    "edits": edits,
+                })
+            })
+            .collect::<Vec<_>>();
+        changes.push(json!({
+            "kind": "rename",
+            "oldUri": self.uri(old_name),
+            "newUri": self.uri(&new_name),
+        }));
+        Ok(json!({ "documentChanges": changes }))
+    }
+
+    /// The note of the document in `params` and the byte offset of the
+    /// position in it.
+    fn cursor(&self, params: &Value) -> Option<(&Note, usize)> {
+        let name = self.name(&params["textDocument"])?;
+        let note = self.index.get(&name)?;
+        let offset = offset(&note.content, &params["position"])?;
+        Some((note, offset))
+    }
+
+    /// Links in all notes that resolve to the note `name`.
+    fn links_to<'a>(&'a self, name: &'a str) -> impl Iterator<Item = (&'a Note, &'a WikiLink)> {
+        self.index.notes.iter().flat_map(move |source| {
+            source
+                .links
+                .iter()
+                .filter(move |l| {
+                    self.index.resolve(&l.target).map(|t| t.name.as_str()) == Some(name)
+                })
+                .map(move |l| (source, l))
+        })
+    }
+
+    fn location(&self, note: &Note, start: usize, end: usize) -> Value {
+        json!({
+            "uri": self.uri(&note.name),
+            "range": range(&note.content, start, end),
+        })
+    }
+
+    fn uri(&self, name: &str) -> String {
+        format!(
+            "file://{}",
+            path::encode(&self.root.join(name).to_string_lossy())
+        )
+    }
+
+    /// Name of the note of a `TextDocumentIdentifier`, `None` for documents
+    /// outside the workspace.
+    fn name(&self, document: &Value) -> Option<String> {
+        let path = canonical(&uri_path(document["uri"].as_str()?)?);
+        let name = path.strip_prefix(&self.root).ok()?;
+        Some(name.to_str()?.to_string())
+    }
+}
+
+fn scan(store: &FsStore) -> Index {
+    Index::scan(store).unwrap_or_else(|e| {
+        eprintln!("failed to read notes: {}", e);
+        Index { notes: Vec::new() }
+    })
+}
+
+fn uri_path(uri: &str) -> Option<PathBuf> {
+    let path = path::decode(uri.strip_prefix("file://")?)?;
+    Some(Path::new(&path).to_path_buf())
+}
+
+/// `path` with symbolic links resolved, so that documents are found below
+/// the root however either is given. Documents that are not saved yet are
+/// resolved through their directory.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(p) = std::fs::canonicalize(path) {
+        return p;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(file_name)) => canonical(parent).join(file_name),
+        _ => path.to_path_buf(),
+    }
+}
+
+fn link_at(note: &Note, offset: usize) -> Option<&WikiLink> {
+    note.links
+        .iter()
+        .find(|l| l.start <= offset && offset <= l.end)
+}
+
+/// `text` of `link` pointing to `new_name` instead, keeping the heading after
+/// `#` and the label. Links that only gave the file name keep doing so.
+fn rewrite_link(text: &str, link: &WikiLink, new_name: &str) -> String {
+    let inner = &text[2..text.len() - 2];
+    let (target, label) = match inner.find('|') {
+        Some(bar) => (&inner[..bar], &inner[bar..]),
+        None => (inner, ""),
+    };
+    let fragment = target
+        .find('#')
+        .map(|i| target[i..].trim_end())
+        .unwrap_or("");
+    let stem = links::stem(new_name);
+    let new_target = if link.target.contains('/') {
+        stem
+    } else {
+        stem.rsplit('/').next().unwrap_or(stem)
+    };
+    format!("[[{}{}{}]]", new_target, fragment, label)
+}
+
+/// LSP range between the byte offsets `start` and `end` of `content`.
+fn range(content: &str, start: usize, end: usize) -> Value {
+    json!({ "start": position(content, start), "end": position(content, end) })
+}
+
+/// LSP position of the byte `offset` of `content`: its line and its column
+/// in UTF-16 code units.
+fn position(content: &str, offset: usize) -> Value {
+    let before = &content[..offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+/// Byte offset of an LSP `position` in `content`.
+fn offset(content: &str, position: &Value) -> Option<usize> {
+    let line = position["line"].as_u64()? as usize;
+    let character = position["character"].as_u64()? as usize;
+    let line_start = match line {
+        0 => 0,
+        _ => content.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+    let mut units = 0;
+    for (i, c) in content[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(content.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "# Über\nsee [[😀 note]]\n\nend";
+
+    #[test]
+    fn position_counts_utf16_units() {
+        assert_eq!(position(CONTENT, 0), json!({ "line": 0, "character": 0 }));
+        // `Ü` is two bytes but one UTF-16 unit.
+        assert_eq!(position(CONTENT, 4), json!({ "line": 0, "character": 3 }));
+        // The emoji is four bytes and two UTF-16 units.
+        let after_emoji = CONTENT.find(" note").unwrap();
+        assert_eq!(
+            position(CONTENT, after_emoji),
+            json!({ "line": 1, "character": 8 })
+        );
+        assert_eq!(
+            position(CONTENT, CONTENT.len()),
+            json!({ "line": 3, "character": 3 })
+        );
+    }
+
+    #[test]
+    fn offset_inverts_position() {
+        for (i, _) in CONTENT.char_indices() {
+            assert_eq!(offset(CONTENT, &position(CONTENT, i)), Some(i));
+        }
+        assert_eq!(
+            offset(CONTENT, &position(CONTENT, CONTENT.len())),
+            Some(CONTENT.len())
+        );
+    }
+
+    #[test]
+    fn offset_clamps_to_the_line() {
+        let line_end = CONTENT.find('\n').unwrap();
+        let position = json!({ "line": 0, "character": 100 });
+        assert_eq!(offset(CONTENT, &position), Some(line_end));
+        assert_eq!(offset(CONTENT, &json!({ "line": 9, "character": 0 })), None);
+        assert_eq!(offset(CONTENT, &json!({ "line": 0 })), None);
+    }
+}
@@ -1,13 +1,219 @@
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast::RecvError;
+use webview_official::{SizeHint, WebviewMut};
+
+use common::api;
+use common::{AppCommand, CaptureRequest, WindowRequest};
+#[cfg(unix)]
+use scrapnote::instance::{self, Instance};
+use scrapnote::Bridge;
+
+/// Name of the binding the frontend calls the API through.
+const IPC_BINDING: &str = "scrapnote_ipc";
+/// Name of the binding the frontend sends [`WindowRequest`]s through.
+const WINDOW_BINDING: &str = "scrapnote_window";
+/// Function the frontend registers to receive file events.
+const IPC_EVENT_HANDLER: &str = "scrapnote_ipc_event";
+/// Function the frontend registers to receive [`AppCommand`]s.
+const IPC_COMMAND_HANDLER: &str = "scrapnote_ipc_command";
+/// Queues commands sent before the frontend has registered its handler.
+const QUEUE_COMMANDS: &str = "window.scrapnote_ipc_command = function (command) {
+    (window.scrapnote_pending_commands = window.scrapnote_pending_commands || []).push(command);
+};";
+
 #[tokio::main]
 async fn main() {
-    let (port, _) = scrapnote::start_server(None, scrapnote::Config::default());
-    webview_official::WebviewBuilder::new()
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    // A second launch passes `--open NOTE` and `--capture TEXT` on to the
+    // window that is open already. The instances find each other through a
+    // Unix socket, so every launch opens its own window elsewhere.
+    #[cfg(unix)]
+    let listener = match Instance::claim().await {
+        Ok(Instance::Primary(listener)) => Some(listener),
+        Ok(Instance::Secondary(stream)) => match instance::forward(stream, args.clone()).await {
+            Ok(Ok(())) => return,
+            Ok(Err(e)) => {
+                eprintln!("scrapnote: {}", e);
+                std::process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("scrapnote: failed to reach the running instance: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Err(e) => {
+            eprintln!("cannot check for a running instance: {}", e);
+            None
+        }
+    };
+
+    // The window talks to the API through the webview bridge. Pass
+    // `--http-api` to also serve it over HTTP, e.g. to use a browser.
+    let http_api = args.iter().any(|a| a == "--http-api");
+    let mut config = scrapnote::Config::default();
+    config.vaults = scrapnote::vault_args(&args);
+    if let Some((width, height)) = scrapnote::saved_window_size() {
+        config.window.width = width;
+        config.window.height = height;
+    }
+    // `--devtools` enables the web inspector in release builds.
+    if args.iter().any(|a| a == "--devtools") {
+        config.window.devtools = true;
+    }
+    let window = config.window.clone();
+    let (server, bridge) = scrapnote::start_webview_server(None, config, http_api);
+    if !server.ready().await {
+        eprintln!("scrapnote: the server failed to start");
+        server.shutdown().await;
+        std::process::exit(1);
+    }
+    let url = format!(
+        "http://127.0.0.1:{}/?token={}",
+        server.addr().port(),
+        bridge.token()
+    );
+    let mut webview = webview_official::WebviewBuilder::new()
         .title("scrapnote")
-        .height(600)
-        .width(500)
-        .resize(webview_official::SizeHint::FIXED)
-        .debug(true)
-        .url(&format!("http://127.0.1:{}", port))
-        .build()
-        .run();
+        .height(window.height)
+        .width(window.width)
+        .resize(if window.resizable {
+            SizeHint::NONE
+        } else {
+            SizeHint::FIXED
+        })
+        .debug(window.devtools)
+        .init(QUEUE_COMMANDS)
+        .url(&url)
+        .build();
+
+    let runtime = tokio::runtime::Handle::current();
+    let webview_ref = webview.as_mut();
+    let call_bridge = bridge.clone();
+    webview.bind(IPC_BINDING, move |seq, req| {
+        let bridge = call_bridge.clone();
+        let mut webview_ref = webview_ref.clone();
+        let seq = seq.to_string();
+        let req = req.to_string();
+        runtime.spawn(async move {
+            let response = bridge.call(&req).await;
+            // The webview may only be touched from its own thread.
+            let _ = webview_ref.dispatch(move |w| w.r#return(&seq, 0, &response));
+        });
+    });
+
+    // Size of the window after the last resize, saved when it is closed.
+    let size = Arc::new(Mutex::new(None));
+    let window_ref = webview.as_mut();
+    let window_size = size.clone();
+    webview.bind(WINDOW_BINDING, move |seq, req| {
+        let mut webview_ref = window_ref.clone();
+        let seq = seq.to_string();
+        match serde_json::from_str::<(WindowRequest,)>(req) {
+            Ok((WindowRequest::SetTitle(title),)) => {
+                let _ = webview_ref.dispatch(move |w| w.set_title(&title));
+            }
+            Ok((WindowRequest::Resized { width, height },)) => {
+                *window_size.lock().unwrap() = Some((width, height));
+            }
+            Err(e) => println!("invalid window request: {}", e),
+        }
+        let _ = webview_ref.dispatch(move |w| w.r#return(&seq, 0, "null"));
+    });
+
+    let mut webview_ref = webview.as_mut();
+    let mut events = bridge.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(e) => e,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+            let data = serde_json::to_string(&event).expect("serialize FileEvent");
+            let script = format!(
+                "window.{} && window.{}({})",
+                IPC_EVENT_HANDLER,
+                IPC_EVENT_HANDLER,
+                serde_json::to_string(&data).expect("serialize event data")
+            );
+            if webview_ref.dispatch(move |w| w.eval(&script)).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut webview_ref = webview.as_mut();
+    let own_bridge = bridge.clone();
+    tokio::spawn(async move {
+        if let Err(e) = handle_args(&args, &own_bridge, &mut webview_ref).await {
+            eprintln!("scrapnote: {}", e);
+        }
+    });
+    #[cfg(unix)]
+    let is_primary = listener.is_some();
+    #[cfg(unix)]
+    if let Some(listener) = listener {
+        match instance::listen(listener) {
+            Ok(mut forwarded) => {
+                let mut webview_ref = webview.as_mut();
+                tokio::spawn(async move {
+                    while let Some(f) = forwarded.recv().await {
+                        let result = handle_args(&f.args, &bridge, &mut webview_ref).await;
+                        f.reply(result);
+                    }
+                });
+            }
+            Err(e) => eprintln!("cannot listen for later launches: {}", e),
+        }
+    }
+
+    webview.run();
+    if let Some((width, height)) = *size.lock().unwrap() {
+        if let Err(e) = scrapnote::save_window_size(width, height) {
+            eprintln!("failed to remember the window size: {}", e);
+        }
+    }
+    #[cfg(unix)]
+    if is_primary {
+        instance::release();
+    }
+    // The window is closed; let saves still in flight finish.
+    server.shutdown().await;
+}
+
+/// Carries out `--capture TEXT` and `--open NOTE` in `args`, given to this
+/// launch or forwarded by a later one.
+async fn handle_args(
+    args: &[String],
+    bridge: &Bridge,
+    webview: &mut WebviewMut,
+) -> Result<(), String> {
+    let option = |name: &str| {
+        args.iter()
+            .position(|a| a == name)
+            .and_then(|i| args.get(i + 1))
+            .cloned()
+    };
+    if let Some(text) = option("--capture") {
+        let response = bridge
+            .request(api::Capture(CaptureRequest {
+                text,
+                note: None,
+                heading: None,
+            }))
+            .await?;
+        println!("captured to {}", response.name);
+    }
+    if let Some(name) = option("--open") {
+        let command = serde_json::to_string(&AppCommand::Open(name)).expect("serialize AppCommand");
+        let script = format!(
+            "window.{}({})",
+            IPC_COMMAND_HANDLER,
+            serde_json::to_string(&command).expect("serialize command data")
+        );
+        webview
+            .dispatch(move |w| w.eval(&script))
+            .map_err(|e| format!("failed to reach the window: {:?}", e))?;
+    }
+    Ok(())
 }
@@ -0,0 +1,239 @@
+use hyper::header::ALLOW;
+use hyper::{Body, Method, Request, Response};
+use serde::de::DeserializeOwned;
+
+use common::{api, path};
+
+/// Matches requests against a list of routes. In a pattern `{name}` matches
+/// one path segment and `{*name}` the rest of the path, which must not be
+/// empty. Parameters are percent-decoded as in [`common::path`], so the rest
+/// of the path doesn't match if it contains a `..` directory.
+pub struct Router<T> {
+    routes: Vec<Route<T>>,
+}
+
+struct Route<T> {
+    method: Method,
+    segments: Vec<Segment>,
+    target: T,
+}
+
+enum Segment {
+    Literal(String),
+    Param(String),
+    Rest(String),
+}
+
+pub enum Match<T> {
+    Found(T, Params),
+    /// The path matched, but only for other methods.
+    MethodNotAllowed(Vec<Method>),
+    NotFound,
+}
+
+/// Path parameters of a matched route.
+pub struct Params(Vec<(String, String)>);
+
+impl Params {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl<T: Copy> Router<T> {
+    pub fn new() -> Self {
+        Router { routes: Vec::new() }
+    }
+
+    pub fn route(mut self, method: Method, pattern: &str, target: T) -> Self {
+        let segments = pattern
+            .trim_start_matches('/')
+            .split('/')
+            .map(
+                |s| match s.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                    Some(name) => match name.strip_prefix('*') {
+                        Some(name) => Segment::Rest(name.to_string()),
+                        None => Segment::Param(name.to_string()),
+                    },
+                    None => Segment::Literal(s.to_string()),
+                },
+            )
+            .collect();
+        self.routes.push(Route {
+            method,
+            segments,
+            target,
+        });
+        self
+    }
+
+    /// Adds the route of the API endpoint `E` as described in
+    /// [`common::api`].
+    pub fn endpoint<E: api::Endpoint>(self, target: T) -> Self {
+        let method = match E::METHOD {
+            api::Method::Get => Method::GET,
+            api::Method::Post => Method::POST,
+            api::Method::Delete => Method::DELETE,
+        };
+        self.route(method, E::PATTERN, target)
+    }
+
+    pub fn find(&self, method: &Method, path: &str) -> Match<T> {
+        let mut allowed = Vec::new();
+        for route in &self.routes {
+            let params = match route.matches(path) {
+                Some(p) => p,
+                None => continue,
+            };
+            if route.method == method {
+                return Match::Found(route.target, params);
+            }
+            allowed.push(route.method.clone());
+        }
+        if allowed.is_empty() {
+            Match::NotFound
+        } else {
+            Match::MethodNotAllowed(allowed)
+        }
+    }
+}
+
+impl<T> Route<T> {
+    fn matches(&self, path: &str) -> Option<Params> {
+        let mut parts = path.trim_start_matches('/').split('/');
+        let mut params = Vec::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => {
+                    if parts.next()? != literal {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    let part = parts.next().filter(|p| !p.is_empty())?;
+                    params.push((name.clone(), path::decode_component(part)?));
+                }
+                Segment::Rest(name) => {
+                    let rest = parts.by_ref().collect::<Vec<_>>();
+                    if rest.iter().all(|p| p.is_empty()) {
+                        return None;
+                    }
+                    params.push((name.clone(), path::decode(&rest.join("/"))?));
+                }
+            }
+        }
+        if parts.next().is_some() {
+            return None;
+        }
+        Some(Params(params))
+    }
+}
+
+/// Parses the query of `req` into `T`. A missing query parses like an empty
+/// one.
+pub fn query<T: DeserializeOwned>(req: &Request<Body>) -> Result<T, serde_urlencoded::de::Error> {
+    serde_urlencoded::from_str(req.uri().query().unwrap_or(""))
+}
+
+pub fn method_not_allowed(allowed: &[Method]) -> Response<Body> {
+    let allowed = allowed
+        .iter()
+        .map(|m| m.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Response::builder()
+        .status(405)
+        .header(ALLOW, allowed)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn router() -> Router<&'static str> {
+        Router::new()
+            .route(Method::GET, "/api/files", "list")
+            .route(Method::GET, "/api/file/{*name}", "get")
+            .route(Method::POST, "/api/file/{*name}", "save")
+            .route(Method::GET, "/api/attachments/{id}", "attachment")
+    }
+
+    fn found(method: Method, path: &str) -> Option<(&'static str, Params)> {
+        match router().find(&method, path) {
+            Match::Found(target, params) => Some((target, params)),
+            _ => None,
+        }
+    }
+
+    fn is_not_found(method: Method, path: &str) -> bool {
+        matches!(router().find(&method, path), Match::NotFound)
+    }
+
+    #[test]
+    fn matches_literals_and_parameters() {
+        assert_eq!(found(Method::GET, "/api/files").unwrap().0, "list");
+        let (target, params) = found(Method::GET, "/api/file/inbox/todo.md").unwrap();
+        assert_eq!(target, "get");
+        assert_eq!(params.get("name"), Some("inbox/todo.md"));
+        let (target, params) = found(Method::GET, "/api/attachments/abc.png").unwrap();
+        assert_eq!(target, "attachment");
+        assert_eq!(params.get("id"), Some("abc.png"));
+        assert!(is_not_found(Method::GET, "/api/filesXYZ"));
+        assert!(is_not_found(Method::GET, "/api/files/extra"));
+        assert!(is_not_found(Method::GET, "/api/attachments/a/b"));
+    }
+
+    #[test]
+    fn trailing_slashes_do_not_match_empty_parameters() {
+        assert!(is_not_found(Method::GET, "/api/files/"));
+        assert!(is_not_found(Method::GET, "/api/file/"));
+        assert!(is_not_found(Method::GET, "/api/file//"));
+        assert!(is_not_found(Method::GET, "/api/attachments/"));
+    }
+
+    #[test]
+    fn other_methods_are_not_allowed_on_known_paths() {
+        match router().find(&Method::DELETE, "/api/file/todo.md") {
+            Match::MethodNotAllowed(allowed) => {
+                assert_eq!(allowed, vec![Method::GET, Method::POST])
+            }
+            _ => panic!("expected 405 for DELETE"),
+        }
+        assert!(matches!(
+            router().find(&Method::POST, "/api/files"),
+            Match::MethodNotAllowed(_)
+        ));
+        assert!(is_not_found(Method::DELETE, "/api/unknown"));
+
+        let response = method_not_allowed(&[Method::GET, Method::POST]);
+        assert_eq!(response.status(), 405);
+        assert_eq!(response.headers()[ALLOW], "GET, POST");
+    }
+
+    #[test]
+    fn parameters_are_percent_decoded() {
+        let (_, params) = found(Method::GET, "/api/file/dir/a%20b%23c%3F.md").unwrap();
+        assert_eq!(params.get("name"), Some("dir/a b#c?.md"));
+        let (_, params) = found(Method::GET, "/api/file/%E3%83%A1%E3%83%A2.md").unwrap();
+        assert_eq!(params.get("name"), Some("メモ.md"));
+        let (_, params) = found(Method::GET, "/api/attachments/a%2Fb").unwrap();
+        assert_eq!(params.get("id"), Some("a/b"));
+        assert!(is_not_found(Method::GET, "/api/file/%FF.md"));
+        assert!(is_not_found(Method::GET, "/api/attachments/%C3"));
+    }
+
+    #[test]
+    fn parent_directories_do_not_match() {
+        assert!(is_not_found(Method::GET, "/api/file/../secret.md"));
+        assert!(is_not_found(
+            Method::GET,
+            "/api/file/a/%2E%2E/%2E%2E/secret.md"
+        ));
+        assert!(is_not_found(Method::POST, "/api/file/..%2Fsecret.md"));
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::Local;
+use std::io::ErrorKind;
+
+use common::links;
+
+use crate::config::Config;
+use crate::store::NoteStore;
+
+const SCRATCH_PREFIX: &str = "scratch-";
+const SCRATCH_EXTENSION: &str = ".md";
+
+/// Creates an empty, timestamped note in the inbox and returns its name.
+pub fn create(store: &dyn NoteStore, config: &Config) -> std::io::Result<String> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut suffix = 1;
+    loop {
+        let file_name = if suffix == 1 {
+            format!("{}{}{}", SCRATCH_PREFIX, stamp, SCRATCH_EXTENSION)
+        } else {
+            format!("{}{}-{}{}", SCRATCH_PREFIX, stamp, suffix, SCRATCH_EXTENSION)
+        };
+        let name = format!("{}/{}", config.inbox_directory, file_name);
+        match store.create(&name) {
+            Ok(_) => return Ok(name),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => suffix += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Called after `name` was written with `content`. Scratch notes are removed
+/// when empty and renamed from their first heading otherwise. Returns the
+/// name the note ends up with.
+pub fn on_saved(
+    store: &dyn NoteStore,
+    config: &Config,
+    name: &str,
+    content: &str,
+) -> std::io::Result<Option<String>> {
+    if !is_scratch(config, name) {
+        return Ok(Some(name.to_string()));
+    }
+    if content.trim().is_empty() {
+        store.delete(name)?;
+        return Ok(None);
+    }
+    let title = match first_heading(content) {
+        Some(t) => t,
+        None => return Ok(Some(name.to_string())),
+    };
+    let new_name = available_name(store, config, &title);
+    store.rename(name, &new_name)?;
+    Ok(Some(new_name))
+}
+
+/// Removes scratch notes that were left empty, e.g. when the app was closed
+/// before the note was saved.
+pub fn cleanup(store: &dyn NoteStore, config: &Config) {
+    let entries = match store.list(&config.inbox_directory) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    for entry in entries.into_iter().filter(|e| !e.is_directory) {
+        if !is_scratch(config, &entry.name) {
+            continue;
+        }
+        let is_empty = store
+            .read_to_string(&entry.name)
+            .map(|c| c.trim().is_empty())
+            .unwrap_or(false);
+        if is_empty {
+            println!("remove empty scratch note: {}", entry.name);
+            if let Err(e) = store.delete(&entry.name) {
+                eprintln!("{}", e);
+            }
+        }
+    }
+}
+
+fn is_scratch(config: &Config, name: &str) -> bool {
+    match name.strip_prefix(&format!("{}/", config.inbox_directory)) {
+        Some(file_name) => file_name.starts_with(SCRATCH_PREFIX) && !file_name.contains('/'),
+        None => false,
+    }
+}
+
+/// Title of the first heading, made safe for a file name. Tags and lines in
+/// code blocks are not headings.
+fn first_heading(content: &str) -> Option<String> {
+    let heading = links::headings(content).into_iter().next()?;
+    let title = heading
+        .title
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            c => c,
+        })
+        .collect::<String>();
+    let title = title.trim_start_matches('.').trim();
+    if title.is_empty() {
+        None
+    } else {
+        Some(title.to_string())
+    }
+}
+
+fn available_name(store: &dyn NoteStore, config: &Config, title: &str) -> String {
+    let mut suffix = 1;
+    loop {
+        let name = if suffix == 1 {
+            format!("{}/{}{}", config.inbox_directory, title, SCRATCH_EXTENSION)
+        } else {
+            format!(
+                "{}/{} ({}){}",
+                config.inbox_directory, title, suffix, SCRATCH_EXTENSION
+            )
+        };
+        if !store.exists(&name) {
+            return name;
+        }
+        suffix += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::InMemoryStore;
+
+    fn saved(store: &InMemoryStore, config: &Config, content: &str) -> Option<String> {
+        let name = create(store, config).unwrap();
+        store.write(&name, content.as_bytes()).unwrap();
+        on_saved(store, config, &name, content).unwrap()
+    }
+
+    #[test]
+    fn renames_scratch_note_after_first_heading() {
+        let store = InMemoryStore::new();
+        let config = Config::default();
+        let content = "#tag\n```\n# In code\n```\n# Plans: 2021\n";
+        let name = saved(&store, &config, content).unwrap();
+        assert_eq!(name, format!("{}/Plans- 2021.md", config.inbox_directory));
+        assert_eq!(store.read_to_string(&name).unwrap(), content);
+
+        let name = saved(&store, &config, "# Plans: 2021\n").unwrap();
+        assert_eq!(
+            name,
+            format!("{}/Plans- 2021 (2).md", config.inbox_directory)
+        );
+    }
+
+    #[test]
+    fn keeps_scratch_note_without_heading_and_removes_empty_one() {
+        let store = InMemoryStore::new();
+        let config = Config::default();
+        let name = saved(&store, &config, "no heading\n").unwrap();
+        assert!(is_scratch(&config, &name));
+
+        assert_eq!(saved(&store, &config, " \n"), None);
+        assert_eq!(store.list(&config.inbox_directory).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn leaves_other_notes_alone() {
+        let store = InMemoryStore::new();
+        let config = Config::default();
+        store.write("ideas.md", b"# Ideas\n").unwrap();
+        let name = on_saved(&store, &config, "ideas.md", "# Ideas\n").unwrap();
+        assert_eq!(name.as_deref(), Some("ideas.md"));
+    }
+}
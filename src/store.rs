@@ -0,0 +1,413 @@
+use std::collections::BTreeMap;
+use std::io::{Error, ErrorKind, Read, Result, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast::Sender;
+
+use common::{FileEvent, FileEventKind};
+
+use crate::watcher;
+
+/// An entry of a directory in a [`NoteStore`].
+pub struct Entry {
+    /// Name relative to the root of the store, e.g. `inbox/todo.md`.
+    pub name: String,
+    pub is_directory: bool,
+}
+
+pub struct Metadata {
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl Metadata {
+    /// Modification time in milliseconds since the epoch, as the API reports
+    /// it.
+    pub fn modified_millis(&self) -> Option<u64> {
+        let since_epoch = self.modified?.duration_since(UNIX_EPOCH).ok()?;
+        Some(since_epoch.as_millis() as u64)
+    }
+}
+
+/// Where notes and attachments are kept. Names are `/`-separated paths
+/// relative to the root of the store; names that would leave it are refused.
+pub trait NoteStore: Send + Sync {
+    /// Entries directly below `directory`, `""` being the root. A directory
+    /// that doesn't exist has no entries.
+    fn list(&self, directory: &str) -> Result<Vec<Entry>>;
+
+    /// Calls `visit` with the entries directly below `directory` as they are
+    /// read, stopping early when it returns `false`.
+    fn visit(&self, directory: &str, visit: &mut dyn FnMut(Entry) -> bool) -> Result<()> {
+        for entry in self.list(directory)? {
+            if !visit(entry) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>>;
+
+    /// Up to `len` bytes of `name` starting at byte `start`.
+    fn read_range(&self, name: &str, start: u64, len: usize) -> Result<Vec<u8>> {
+        let content = self.read(name)?;
+        let start = (start as usize).min(content.len());
+        let end = start.saturating_add(len).min(content.len());
+        Ok(content[start..end].to_vec())
+    }
+
+    /// Creates or replaces `name`, creating missing parent directories.
+    fn write(&self, name: &str, content: &[u8]) -> Result<()>;
+
+    /// Creates `name` empty, failing with `ErrorKind::AlreadyExists` if it
+    /// exists.
+    fn create(&self, name: &str) -> Result<()>;
+
+    fn delete(&self, name: &str) -> Result<()>;
+
+    fn rename(&self, from: &str, to: &str) -> Result<()>;
+
+    fn stat(&self, name: &str) -> Result<Metadata>;
+
+    /// Broadcasts changes to the files in the store through `events`,
+    /// including changes made by other programs where the store can tell.
+    fn watch(&self, events: Sender<FileEvent>);
+
+    fn read_to_string(&self, name: &str) -> Result<String> {
+        String::from_utf8(self.read(name)?).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.stat(name).is_ok()
+    }
+}
+
+/// Keeps notes as files below a directory.
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FsStore { root: root.into() }
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        Ok(self.root.join(check_name(name)?))
+    }
+}
+
+impl NoteStore for FsStore {
+    fn list(&self, directory: &str) -> Result<Vec<Entry>> {
+        let mut result = Vec::new();
+        self.visit(directory, &mut |entry| {
+            result.push(entry);
+            true
+        })?;
+        Ok(result)
+    }
+
+    fn visit(&self, directory: &str, visit: &mut dyn FnMut(Entry) -> bool) -> Result<()> {
+        let directory = normalized(directory)?;
+        let prefix = if directory.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", directory)
+        };
+        let entries = match std::fs::read_dir(self.root.join(&directory)) {
+            Ok(e) => e,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        for entry in entries {
+            let entry = entry?;
+            let file_name = match entry.file_name().into_string() {
+                Ok(f) => f,
+                Err(_) => continue,
+            };
+            let file_type = entry.file_type()?;
+            if !file_type.is_dir() && !file_type.is_file() {
+                continue;
+            }
+            let entry = Entry {
+                name: format!("{}{}", prefix, file_name),
+                is_directory: file_type.is_dir(),
+            };
+            if !visit(entry) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        std::fs::read(self.path(name)?)
+    }
+
+    fn read_range(&self, name: &str, start: u64, len: usize) -> Result<Vec<u8>> {
+        let mut file = std::fs::File::open(self.path(name)?)?;
+        file.seek(SeekFrom::Start(start))?;
+        let mut result = Vec::with_capacity(len);
+        file.take(len as u64).read_to_end(&mut result)?;
+        Ok(result)
+    }
+
+    fn write(&self, name: &str, content: &[u8]) -> Result<()> {
+        let path = self.path(name)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)
+    }
+
+    fn create(&self, name: &str) -> Result<()> {
+        let path = self.path(name)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        std::fs::remove_file(self.path(name)?)
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<()> {
+        let to = self.path(to)?;
+        if let Some(parent) = to.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::rename(self.path(from)?, to)
+    }
+
+    fn stat(&self, name: &str) -> Result<Metadata> {
+        let metadata = std::fs::metadata(self.path(name)?)?;
+        Ok(Metadata {
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn watch(&self, events: Sender<FileEvent>) {
+        watcher::spawn(&self.root, events);
+    }
+}
+
+/// Keeps notes in memory, e.g. for tests or when scrapnote is embedded in
+/// another tool. Only changes made through the store are reported to
+/// [`NoteStore::watch`].
+#[derive(Default)]
+pub struct InMemoryStore {
+    files: Mutex<BTreeMap<String, (Vec<u8>, SystemTime)>>,
+    events: Mutex<Vec<Sender<FileEvent>>>,
+}
+
+impl InMemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn notify(&self, kind: FileEventKind, name: &str) {
+        for events in self.events.lock().unwrap().iter() {
+            // Sending only fails when nobody is subscribed.
+            let _ = events.send(FileEvent {
+                kind: kind.clone(),
+                name: name.to_string(),
+                vault: String::new(),
+            });
+        }
+    }
+}
+
+impl NoteStore for InMemoryStore {
+    fn list(&self, directory: &str) -> Result<Vec<Entry>> {
+        let directory = normalized(directory)?;
+        let prefix = if directory.is_empty() {
+            directory
+        } else {
+            format!("{}/", directory)
+        };
+        let mut result: Vec<Entry> = Vec::new();
+        for name in self.files.lock().unwrap().keys() {
+            let rest = match name.strip_prefix(&prefix) {
+                Some(r) => r,
+                None => continue,
+            };
+            let entry = match rest.split_once('/') {
+                Some((child, _)) => Entry {
+                    name: format!("{}{}", prefix, child),
+                    is_directory: true,
+                },
+                None => Entry {
+                    name: name.clone(),
+                    is_directory: false,
+                },
+            };
+            if !result.iter().any(|e| e.name == entry.name) {
+                result.push(entry);
+            }
+        }
+        Ok(result)
+    }
+
+    fn read(&self, name: &str) -> Result<Vec<u8>> {
+        let name = normalized(name)?;
+        match self.files.lock().unwrap().get(&name) {
+            Some((content, _)) => Ok(content.clone()),
+            None => Err(not_found(&name)),
+        }
+    }
+
+    fn read_range(&self, name: &str, start: u64, len: usize) -> Result<Vec<u8>> {
+        let name = normalized(name)?;
+        match self.files.lock().unwrap().get(&name) {
+            Some((content, _)) => {
+                let start = (start as usize).min(content.len());
+                let end = start.saturating_add(len).min(content.len());
+                Ok(content[start..end].to_vec())
+            }
+            None => Err(not_found(&name)),
+        }
+    }
+
+    fn write(&self, name: &str, content: &[u8]) -> Result<()> {
+        let name = normalized(name)?;
+        let previous = self
+            .files
+            .lock()
+            .unwrap()
+            .insert(name.clone(), (content.to_vec(), SystemTime::now()));
+        let kind = match previous {
+            Some(_) => FileEventKind::Changed,
+            None => FileEventKind::Created,
+        };
+        self.notify(kind, &name);
+        Ok(())
+    }
+
+    fn create(&self, name: &str) -> Result<()> {
+        let name = normalized(name)?;
+        {
+            let mut files = self.files.lock().unwrap();
+            if files.contains_key(&name) {
+                return Err(Error::new(ErrorKind::AlreadyExists, name));
+            }
+            files.insert(name.clone(), (Vec::new(), SystemTime::now()));
+        }
+        self.notify(FileEventKind::Created, &name);
+        Ok(())
+    }
+
+    fn delete(&self, name: &str) -> Result<()> {
+        let name = normalized(name)?;
+        if self.files.lock().unwrap().remove(&name).is_none() {
+            return Err(not_found(&name));
+        }
+        self.notify(FileEventKind::Removed, &name);
+        Ok(())
+    }
+
+    fn rename(&self, from: &str, to: &str) -> Result<()> {
+        let from = normalized(from)?;
+        let to = normalized(to)?;
+        {
+            let mut files = self.files.lock().unwrap();
+            let file = files.remove(&from).ok_or_else(|| not_found(&from))?;
+            files.insert(to.clone(), file);
+        }
+        self.notify(FileEventKind::Removed, &from);
+        self.notify(FileEventKind::Created, &to);
+        Ok(())
+    }
+
+    fn stat(&self, name: &str) -> Result<Metadata> {
+        let name = normalized(name)?;
+        match self.files.lock().unwrap().get(&name) {
+            Some((content, modified)) => Ok(Metadata {
+                len: content.len() as u64,
+                modified: Some(*modified),
+            }),
+            None => Err(not_found(&name)),
+        }
+    }
+
+    fn watch(&self, events: Sender<FileEvent>) {
+        self.events.lock().unwrap().push(events);
+    }
+}
+
+/// `name` as a relative path, refusing absolute paths and `..`.
+fn check_name(name: &str) -> Result<&Path> {
+    let path = Path::new(name);
+    let valid = path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    if valid {
+        Ok(path)
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("invalid name: {}", name),
+        ))
+    }
+}
+
+/// `name` without `.` and empty segments, so that equal paths have equal
+/// keys in an [`InMemoryStore`].
+fn normalized(name: &str) -> Result<String> {
+    check_name(name)?;
+    Ok(name
+        .split('/')
+        .filter(|s| !s.is_empty() && *s != ".")
+        .collect::<Vec<_>>()
+        .join("/"))
+}
+
+fn not_found(name: &str) -> Error {
+    Error::new(ErrorKind::NotFound, format!("no such note: {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_name_refuses_leaving_the_root() {
+        for name in &["../secret", "a/../../secret", "/etc/passwd", "a/.."] {
+            let error = check_name(name).unwrap_err();
+            assert_eq!(error.kind(), ErrorKind::InvalidInput, "{}", name);
+        }
+        assert!(check_name("inbox/./todo.md").is_ok());
+    }
+
+    #[test]
+    fn normalized_drops_empty_and_current_segments() {
+        assert_eq!(normalized("inbox//./todo.md").unwrap(), "inbox/todo.md");
+        assert_eq!(normalized("./").unwrap(), "");
+        assert!(normalized("inbox/../../todo.md").is_err());
+    }
+
+    #[test]
+    fn stores_refuse_traversal() {
+        let store = InMemoryStore::new();
+        assert!(store.write("../escaped.md", b"x").is_err());
+        assert!(store.read("../escaped.md").is_err());
+        assert!(store.list("..").is_err());
+
+        let store = FsStore::new(std::env::temp_dir().join("scrapnote-test-store"));
+        assert_eq!(
+            store.read("../escaped.md").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+        assert_eq!(
+            store.write("/tmp/escaped.md", b"x").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufReader, Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_rustls::rustls::internal::pemfile;
+use tokio_rustls::rustls::{NoClientAuth, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use crate::config::{config_directory, LanConfig};
+
+/// Clients that don't finish the handshake in time are dropped.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Builds the TLS configuration from the PEM files in `lan`, or from a
+/// self-signed certificate that is generated on first run.
+pub fn server_config(lan: &LanConfig) -> std::io::Result<ServerConfig> {
+    let (certificate, private_key) = match (&lan.certificate, &lan.private_key) {
+        (Some(c), Some(k)) => (PathBuf::from(c), PathBuf::from(k)),
+        (None, None) => self_signed()?,
+        _ => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "both a certificate and a private key are required",
+            ))
+        }
+    };
+    let certs = pemfile::certs(&mut BufReader::new(File::open(&certificate)?))
+        .map_err(|_| invalid_pem(&certificate))?;
+    let mut keys = pemfile::pkcs8_private_keys(&mut BufReader::new(File::open(&private_key)?))
+        .map_err(|_| invalid_pem(&private_key))?;
+    if keys.is_empty() {
+        keys = pemfile::rsa_private_keys(&mut BufReader::new(File::open(&private_key)?))
+            .map_err(|_| invalid_pem(&private_key))?;
+    }
+    if certs.is_empty() || keys.is_empty() {
+        return Err(invalid_pem(&certificate));
+    }
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(certs, keys.remove(0))
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, e))?;
+    Ok(config)
+}
+
+fn invalid_pem(path: &Path) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("no usable PEM data in {}", path.display()),
+    )
+}
+
+/// Paths of the self-signed certificate and its key, generating them if they
+/// don't exist yet.
+fn self_signed() -> std::io::Result<(PathBuf, PathBuf)> {
+    let directory = config_directory().join("tls");
+    let certificate = directory.join("cert.pem");
+    let private_key = directory.join("key.pem");
+    if certificate.exists() && private_key.exists() {
+        return Ok((certificate, private_key));
+    }
+    let mut names = vec!["localhost".to_string()];
+    if let Ok(hostname) = std::fs::read_to_string("/etc/hostname") {
+        let hostname = hostname.trim();
+        if !hostname.is_empty() {
+            names.push(hostname.to_string());
+        }
+    }
+    let generated =
+        rcgen::generate_simple_self_signed(names).map_err(|e| Error::other(e.to_string()))?;
+    let pem = generated
+        .serialize_pem()
+        .map_err(|e| Error::other(e.to_string()))?;
+    std::fs::create_dir_all(&directory)?;
+    std::fs::write(&certificate, pem)?;
+    write_private(&private_key, &generated.serialize_private_key_pem())?;
+    println!(
+        "generated self-signed certificate: {}",
+        certificate.display()
+    );
+    Ok((certificate, private_key))
+}
+
+fn write_private(path: &Path, content: &str) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Accepts connections on `listener` and completes TLS handshakes in the
+/// background, so that a slow client cannot hold up others. The receiver
+/// yields established connections for `hyper::server::accept::from_stream`.
+/// The listener is closed once `shutdown` resolves.
+pub fn incoming(
+    mut listener: TcpListener,
+    config: ServerConfig,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> mpsc::Receiver<Result<TlsStream<TcpStream>, Error>> {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(async move {
+        tokio::pin!(shutdown);
+        loop {
+            let accepted = tokio::select! {
+                accepted = listener.accept() => accepted,
+                _ = &mut shutdown => break,
+            };
+            let (stream, address) = match accepted {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("accept failed: {}", e);
+                    tokio::time::delay_for(Duration::from_millis(100)).await;
+                    continue;
+                }
+            };
+            let acceptor = acceptor.clone();
+            let mut sender = sender.clone();
+            tokio::spawn(async move {
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => {
+                        let _ = sender.send(Ok(stream)).await;
+                    }
+                    Ok(Err(e)) => println!("TLS handshake with {} failed: {}", address, e),
+                    Err(_) => println!("TLS handshake with {} timed out", address),
+                }
+            });
+        }
+    });
+    receiver
+}
@@ -0,0 +1,63 @@
+use notify::{DebouncedEvent, RecursiveMode, Watcher};
+use std::path::Path;
+use std::sync::mpsc::channel;
+use std::time::Duration;
+use tokio::sync::broadcast::Sender;
+
+use common::{FileEvent, FileEventKind};
+
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Watches `directory` recursively on a background thread and broadcasts
+/// changes to its files through `events`.
+pub fn spawn(directory: &Path, events: Sender<FileEvent>) {
+    let directory = directory.to_path_buf();
+    std::thread::spawn(move || {
+        let (tx, rx) = channel();
+        let mut watcher = match notify::watcher(tx, DEBOUNCE) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("failed to create file watcher: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = watcher.watch(&directory, RecursiveMode::Recursive) {
+            eprintln!("failed to watch {}: {}", directory.display(), e);
+            return;
+        }
+        println!("watching {}", directory.display());
+        for event in rx {
+            for event in to_file_events(&directory, event) {
+                // Sending only fails when nobody is subscribed.
+                let _ = events.send(event);
+            }
+        }
+    });
+}
+
+fn to_file_events(directory: &Path, event: DebouncedEvent) -> Vec<FileEvent> {
+    let file_event = |kind, path: &Path| {
+        path.strip_prefix(directory)
+            .ok()
+            .and_then(|p| p.to_str())
+            .map(|name| FileEvent {
+                kind,
+                name: name.to_string(),
+                vault: String::new(),
+            })
+    };
+    match event {
+        DebouncedEvent::Create(p) => file_event(FileEventKind::Created, &p).into_iter().collect(),
+        DebouncedEvent::Write(p) => file_event(FileEventKind::Changed, &p).into_iter().collect(),
+        DebouncedEvent::Remove(p) => file_event(FileEventKind::Removed, &p).into_iter().collect(),
+        DebouncedEvent::Rename(from, to) => file_event(FileEventKind::Removed, &from)
+            .into_iter()
+            .chain(file_event(FileEventKind::Created, &to))
+            .collect(),
+        DebouncedEvent::Error(e, _) => {
+            eprintln!("file watcher error: {}", e);
+            Vec::new()
+        }
+        _ => Vec::new(),
+    }
+}
@@ -1,165 +1,947 @@
-(function () {
-    'use strict';
-
+(function() {
     function get_value() {
         return window.editor.getValue();
     }
-
     function set_value(value) {
         window.editor.setValue(value);
     }
-
     function focus() {
         window.editor.focus();
     }
-
     function set_editable(editable) {
         window.editor.updateOptions({
-            readOnly: !editable,
+            readOnly: !editable
         });
     }
-
-    var __wbg_star0 = /*#__PURE__*/Object.freeze({
-        __proto__: null,
-        get_value: get_value,
-        set_value: set_value,
-        focus: focus,
-        set_editable: set_editable
-    });
-
-    function scroll_list() {
-      let selected = document.querySelector("#list div.selected");
-      if (selected) {
-        selected.scrollIntoView({
-          block: 'center',
+    function withEditor(f) {
+        if (window.editor) {
+            f(window.editor);
+        } else {
+            setTimeout(()=>withEditor(f), 50);
+        }
+    }
+    function on_change(callback) {
+        withEditor((editor)=>editor.onDidChangeModelContent(()=>callback(editor.getValue())));
+    }
+    function on_scroll(callback) {
+        withEditor((editor)=>editor.onDidScrollChange(()=>{
+                let ranges = editor.getVisibleRanges();
+                callback(ranges.length > 0 ? ranges[0].startLineNumber : 1);
+            }));
+    }
+    function on_files(callback) {
+        withEditor((editor)=>{
+            let node = editor.getDomNode();
+            let handle = (e, files)=>{
+                if (!files || files.length === 0) {
+                    return;
+                }
+                e.preventDefault();
+                e.stopPropagation();
+                for(let i = 0; i < files.length; i++){
+                    callback(files[i]);
+                }
+            };
+            node.addEventListener('paste', (e)=>handle(e, e.clipboardData && e.clipboardData.files), true);
+            node.addEventListener('dragover', (e)=>{
+                if (e.dataTransfer && Array.from(e.dataTransfer.types).includes('Files')) {
+                    e.preventDefault();
+                }
+            }, true);
+            node.addEventListener('drop', (e)=>handle(e, e.dataTransfer && e.dataTransfer.files), true);
         });
-      }
     }
-
-    var __wbg_star1 = /*#__PURE__*/Object.freeze({
-        __proto__: null,
-        scroll_list: scroll_list
-    });
-
-    let wasm;
-
-    const heap = new Array(32).fill(undefined);
-
-    heap.push(undefined, null, true, false);
-
-    function getObject(idx) { return heap[idx]; }
-
-    let heap_next = heap.length;
-
-    function dropObject(idx) {
-        if (idx < 36) return;
-        heap[idx] = heap_next;
-        heap_next = idx;
-    }
-
-    function takeObject(idx) {
-        const ret = getObject(idx);
-        dropObject(idx);
-        return ret;
-    }
-
-    function addHeapObject(obj) {
-        if (heap_next === heap.length) heap.push(heap.length + 1);
-        const idx = heap_next;
-        heap_next = heap[idx];
-
-        heap[idx] = obj;
-        return idx;
+    function insert_text(text) {
+        window.editor.executeEdits('scrapnote', [
+            {
+                range: window.editor.getSelection(),
+                text: text,
+                forceMoveMarkers: true
+            }
+        ]);
+        window.editor.focus();
     }
-
-    let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
-
-    cachedTextDecoder.decode();
-
-    let cachegetUint8Memory0 = null;
-    function getUint8Memory0() {
-        if (cachegetUint8Memory0 === null || cachegetUint8Memory0.buffer !== wasm.memory.buffer) {
-            cachegetUint8Memory0 = new Uint8Array(wasm.memory.buffer);
+    const mod = function() {
+        return {
+            get_value: get_value,
+            set_value: set_value,
+            focus: focus,
+            set_editable: set_editable,
+            on_change: on_change,
+            on_scroll: on_scroll,
+            on_files: on_files,
+            insert_text: insert_text
+        };
+    }();
+    function has_window_binding() {
+        return typeof window.scrapnote_window === 'function';
+    }
+    function set_title(title) {
+        if (document.title === title) {
+            return;
+        }
+        document.title = title;
+        if (has_window_binding()) {
+            window.scrapnote_window({
+                SetTitle: title
+            });
         }
-        return cachegetUint8Memory0;
     }
-
-    function getStringFromWasm0(ptr, len) {
-        return cachedTextDecoder.decode(getUint8Memory0().subarray(ptr, ptr + len));
+    function install_resize_listener() {
+        if (!has_window_binding()) {
+            return;
+        }
+        let timer = null;
+        window.addEventListener('resize', ()=>{
+            clearTimeout(timer);
+            timer = setTimeout(()=>window.scrapnote_window({
+                    Resized: {
+                        width: window.innerWidth,
+                        height: window.innerHeight
+                    }
+                }), 500);
+        });
     }
-
-    let WASM_VECTOR_LEN = 0;
-
-    let cachedTextEncoder = new TextEncoder('utf-8');
-
-    const encodeString = (typeof cachedTextEncoder.encodeInto === 'function'
-        ? function (arg, view) {
-        return cachedTextEncoder.encodeInto(arg, view);
-    }
-        : function (arg, view) {
-        const buf = cachedTextEncoder.encode(arg);
-        view.set(buf);
+    const mod1 = function() {
         return {
-            read: arg.length,
-            written: buf.length
+            set_title: set_title,
+            install_resize_listener: install_resize_listener
         };
-    });
-
-    function passStringToWasm0(arg, malloc, realloc) {
-
-        if (realloc === undefined) {
-            const buf = cachedTextEncoder.encode(arg);
-            const ptr = malloc(buf.length);
-            getUint8Memory0().subarray(ptr, ptr + buf.length).set(buf);
-            WASM_VECTOR_LEN = buf.length;
-            return ptr;
+    }();
+    function has_ipc() {
+        return typeof window.scrapnote_ipc === 'function';
+    }
+    function encode(buffer) {
+        let bytes = new Uint8Array(buffer);
+        let binary = '';
+        for(let i = 0; i < bytes.length; i++){
+            binary += String.fromCharCode(bytes[i]);
         }
-
-        let len = arg.length;
-        let ptr = malloc(len);
-
-        const mem = getUint8Memory0();
-
-        let offset = 0;
-
-        for (; offset < len; offset++) {
-            const code = arg.charCodeAt(offset);
-            if (code > 0x7F) break;
-            mem[ptr + offset] = code;
+        return btoa(binary);
+    }
+    function decode(base64) {
+        let binary = atob(base64);
+        let bytes = new Uint8Array(binary.length);
+        for(let i = 0; i < binary.length; i++){
+            bytes[i] = binary.charCodeAt(i);
         }
-
-        if (offset !== len) {
-            if (offset !== 0) {
-                arg = arg.slice(offset);
+        return bytes;
+    }
+    function install_fetch_shim() {
+        if (!has_ipc()) {
+            return;
+        }
+        let fetch1 = window.fetch.bind(window);
+        window.fetch = async function(input, init) {
+            let request = new Request(input, init);
+            let url = new URL(request.url);
+            if (url.origin !== location.origin || !url.pathname.startsWith('/api/')) {
+                return fetch1(input, init);
             }
-            ptr = realloc(ptr, len, len = offset + arg.length * 3);
-            const view = getUint8Memory0().subarray(ptr + offset, ptr + len);
-            const ret = encodeString(arg, view);
-
-            offset += ret.written;
+            let request_body = request.method === 'GET' || request.method === 'HEAD' ? '' : encode(await request.arrayBuffer());
+            let response = await window.scrapnote_ipc({
+                method: request.method,
+                path: url.pathname + url.search,
+                content_type: request.headers.get('Content-Type'),
+                body: request_body
+            });
+            let headers = response.content_type ? {
+                'Content-Type': response.content_type
+            } : {};
+            let body_bytes = response.status === 204 || response.status === 304 ? null : decode(response.body);
+            return new Response(body_bytes, {
+                status: response.status,
+                headers: headers
+            });
+        };
+    }
+    const mod2 = function() {
+        return {
+            has_ipc: has_ipc,
+            install_fetch_shim: install_fetch_shim
+        };
+    }();
+    let listeners = [];
+    let source = null;
+    function dispatch(data) {
+        listeners.forEach((listener)=>listener(data));
+    }
+    function on_command(callback) {
+        let pending = window.scrapnote_pending_commands || [];
+        window.scrapnote_pending_commands = [];
+        window.scrapnote_ipc_command = callback;
+        pending.forEach(callback);
+    }
+    function on_file_event(callback) {
+        listeners.push(callback);
+        if (has_ipc()) {
+            window.scrapnote_ipc_event = dispatch;
+        } else if (!source) {
+            source = new EventSource('/api/events');
+            source.onmessage = (e)=>dispatch(e.data);
         }
-
-        WASM_VECTOR_LEN = offset;
-        return ptr;
     }
-
-    function isLikeNone(x) {
-        return x === undefined || x === null;
+    const mod3 = function() {
+        return {
+            on_command: on_command,
+            on_file_event: on_file_event
+        };
+    }();
+    function scroll_list() {
+        let selected = document.querySelector("#list div.selected");
+        if (selected) {
+            selected.scrollIntoView({
+                block: 'center'
+            });
+        }
+    }
+    const mod4 = function() {
+        return {
+            scroll_list: scroll_list
+        };
+    }();
+    const importMeta = {
+        url: document.currentScript && document.currentScript.src || new URL('scrapnote.js', document.baseURI).href
+    };
+    function run_app() {
+        wasm.run_app();
+    }
+    function __wbg_get_imports() {
+        const import0 = {
+            __proto__: null,
+            __wbg_Window_aeb87eb1c60f66d0: function(arg0) {
+                const ret = arg0.Window;
+                return ret;
+            },
+            __wbg_WorkerGlobalScope_fe0f29478b26e33d: function(arg0) {
+                const ret = arg0.WorkerGlobalScope;
+                return ret;
+            },
+            __wbg___wbindgen_debug_string_4687d8d8c2017d52: function(arg0, arg1) {
+                const ret = debugString(arg1);
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg___wbindgen_is_function_1f9d30630b8b1d3d: function(arg0) {
+                const ret = typeof arg0 === 'function';
+                return ret;
+            },
+            __wbg___wbindgen_is_object_3c45d4f2dde4e749: function(arg0) {
+                const val = arg0;
+                const ret = typeof val === 'object' && val !== null;
+                return ret;
+            },
+            __wbg___wbindgen_is_undefined_8865fb403f8fe9d8: function(arg0) {
+                const ret = arg0 === undefined;
+                return ret;
+            },
+            __wbg___wbindgen_string_get_0380ccaa2f57f0d9: function(arg0, arg1) {
+                const obj = arg1;
+                const ret = typeof obj === 'string' ? obj : undefined;
+                var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                var len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg___wbindgen_throw_41e9ee4f547fc59a: function(arg0, arg1) {
+                throw new Error(getStringFromWasm0(arg0, arg1));
+            },
+            __wbg__wbg_cb_unref_dcc1a90847f04c41: function(arg0) {
+                arg0._wbg_cb_unref();
+            },
+            __wbg_abort_870616b7187e53fb: function(arg0) {
+                arg0.abort();
+            },
+            __wbg_abort_f78b99d80248bacb: function(arg0) {
+                arg0.abort();
+            },
+            __wbg_addEventListener_8d3235978b9c69a8: function() {
+                return handleError(function(arg0, arg1, arg2, arg3, arg4) {
+                    arg0.addEventListener(getStringFromWasm0(arg1, arg2), arg3, arg4);
+                }, arguments);
+            },
+            __wbg_appendChild_fb8c52e7dd8484ea: function() {
+                return handleError(function(arg0, arg1) {
+                    const ret = arg0.appendChild(arg1);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_arrayBuffer_f07be74819944ea2: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.arrayBuffer();
+                    return ret;
+                }, arguments);
+            },
+            __wbg_call_6137034ef55c9d0f: function() {
+                return handleError(function(arg0, arg1) {
+                    const ret = arg0.call(arg1);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_clearTimeout_01406e55473040f6: function(arg0) {
+                const ret = clearTimeout(arg0);
+                return ret;
+            },
+            __wbg_createElementNS_957ec8b66176901e: function() {
+                return handleError(function(arg0, arg1, arg2, arg3, arg4) {
+                    const ret = arg0.createElementNS(arg1 === 0 ? undefined : getStringFromWasm0(arg1, arg2), getStringFromWasm0(arg3, arg4));
+                    return ret;
+                }, arguments);
+            },
+            __wbg_createElement_74049073a11f9c31: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    const ret = arg0.createElement(getStringFromWasm0(arg1, arg2));
+                    return ret;
+                }, arguments);
+            },
+            __wbg_createTextNode_561b59dda21e4ea7: function(arg0, arg1, arg2) {
+                const ret = arg0.createTextNode(getStringFromWasm0(arg1, arg2));
+                return ret;
+            },
+            __wbg_ctrlKey_8b5101745e8782fa: function(arg0) {
+                const ret = arg0.ctrlKey;
+                return ret;
+            },
+            __wbg_debug_05be7ad0a1e623f8: function(arg0, arg1, arg2, arg3) {
+                console.debug(arg0, arg1, arg2, arg3);
+            },
+            __wbg_document_9854e03c05fc8834: function(arg0) {
+                const ret = arg0.document;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_done_b41a1d26cdb37fb6: function(arg0) {
+                const ret = arg0.done;
+                return ret;
+            },
+            __wbg_error_6614f5677eeead43: function(arg0, arg1, arg2, arg3) {
+                console.error(arg0, arg1, arg2, arg3);
+            },
+            __wbg_error_c9cf3fc2064683a9: function(arg0) {
+                console.error(arg0);
+            },
+            __wbg_fetch_276c67b6d656772a: function(arg0, arg1, arg2) {
+                const ret = arg0.fetch(arg1, arg2);
+                return ret;
+            },
+            __wbg_fetch_7b5de4f88137a4dc: function(arg0, arg1, arg2) {
+                const ret = arg0.fetch(arg1, arg2);
+                return ret;
+            },
+            __wbg_firstElementChild_d5206a011a5ecd0c: function(arg0) {
+                const ret = arg0.firstElementChild;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_focus_f740d61348f422e7: function() {
+                return handleError(function(arg0) {
+                    arg0.focus();
+                }, arguments);
+            },
+            __wbg_from_296ca31f8d0f1c52: function(arg0) {
+                const ret = Array.from(arg0);
+                return ret;
+            },
+            __wbg_getAttribute_061ad00c16e2f622: function(arg0, arg1, arg2, arg3) {
+                const ret = arg1.getAttribute(getStringFromWasm0(arg2, arg3));
+                var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                var len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_getItem_4c80b7663197c7ff: function() {
+                return handleError(function(arg0, arg1, arg2, arg3) {
+                    const ret = arg1.getItem(getStringFromWasm0(arg2, arg3));
+                    var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                    var len1 = WASM_VECTOR_LEN;
+                    getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                    getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+                }, arguments);
+            },
+            __wbg_get_658f6698067d9515: function() {
+                return handleError(function(arg0, arg1) {
+                    const ret = Reflect.get(arg0, arg1);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_get_6c896e0571ddae51: function(arg0, arg1) {
+                const ret = arg0[arg1 >>> 0];
+                return ret;
+            },
+            __wbg_get_value_da1b0cbf8ca39560: function(arg0) {
+                const ret = get_value();
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_headers_eba93595f8944c2f: function(arg0) {
+                const ret = arg0.headers;
+                return ret;
+            },
+            __wbg_info_b68ad4a35d1670c2: function(arg0, arg1, arg2, arg3) {
+                console.info(arg0, arg1, arg2, arg3);
+            },
+            __wbg_insertBefore_f1ba67809033f6b1: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    const ret = arg0.insertBefore(arg1, arg2);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_insert_text_6d3ff5847740617a: function(arg0, arg1) {
+                let deferred0_0;
+                let deferred0_1;
+                try {
+                    deferred0_0 = arg0;
+                    deferred0_1 = arg1;
+                    insert_text(getStringFromWasm0(arg0, arg1));
+                } finally{
+                    wasm.__wbindgen_free(deferred0_0, deferred0_1, 1);
+                }
+            },
+            __wbg_instanceof_HtmlButtonElement_bd67486d3501ad48: function(arg0) {
+                let result;
+                try {
+                    result = arg0 instanceof HTMLButtonElement;
+                } catch (_) {
+                    result = false;
+                }
+                const ret = result;
+                return ret;
+            },
+            __wbg_instanceof_HtmlElement_32fb153a5a0e2349: function(arg0) {
+                let result;
+                try {
+                    result = arg0 instanceof HTMLElement;
+                } catch (_) {
+                    result = false;
+                }
+                const ret = result;
+                return ret;
+            },
+            __wbg_instanceof_HtmlInputElement_5c33d1de59c09c49: function(arg0) {
+                let result;
+                try {
+                    result = arg0 instanceof HTMLInputElement;
+                } catch (_) {
+                    result = false;
+                }
+                const ret = result;
+                return ret;
+            },
+            __wbg_instanceof_HtmlTextAreaElement_bbe97f862930488c: function(arg0) {
+                let result;
+                try {
+                    result = arg0 instanceof HTMLTextAreaElement;
+                } catch (_) {
+                    result = false;
+                }
+                const ret = result;
+                return ret;
+            },
+            __wbg_instanceof_Window_82d71df4eddf88bc: function(arg0) {
+                let result;
+                try {
+                    result = arg0 instanceof Window;
+                } catch (_) {
+                    result = false;
+                }
+                const ret = result;
+                return ret;
+            },
+            __wbg_isComposing_600c9236938f8e5a: function(arg0) {
+                const ret = arg0.isComposing;
+                return ret;
+            },
+            __wbg_is_4b278c0bd3caba97: function(arg0, arg1) {
+                const ret = Object.is(arg0, arg1);
+                return ret;
+            },
+            __wbg_iterator_e3c31c892080e444: function() {
+                const ret = Symbol.iterator;
+                return ret;
+            },
+            __wbg_key_1193871533b99ae5: function(arg0, arg1) {
+                const ret = arg1.key;
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_lastChild_bca1746658aaee75: function(arg0) {
+                const ret = arg0.lastChild;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_length_7f3c00c40364105e: function(arg0) {
+                const ret = arg0.length;
+                return ret;
+            },
+            __wbg_localStorage_567c24950edbc178: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.localStorage;
+                    return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+                }, arguments);
+            },
+            __wbg_log_19b94368315af0fb: function(arg0, arg1, arg2, arg3) {
+                console.log(arg0, arg1, arg2, arg3);
+            },
+            __wbg_name_06fefa2542c6ce51: function(arg0, arg1) {
+                const ret = arg1.name;
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_namespaceURI_67f6d39f1a689250: function(arg0, arg1) {
+                const ret = arg1.namespaceURI;
+                var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                var len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_new_1dbf7428bba60a42: function(arg0) {
+                const ret = new Uint8Array(arg0);
+                return ret;
+            },
+            __wbg_new_2e41357094e99294: function() {
+                return handleError(function() {
+                    const ret = new AbortController();
+                    return ret;
+                }, arguments);
+            },
+            __wbg_new_617a8cdb8bb1130e: function() {
+                const ret = new Object();
+                return ret;
+            },
+            __wbg_new_ae4555a03ebb1118: function() {
+                return handleError(function() {
+                    const ret = new FileReader();
+                    return ret;
+                }, arguments);
+            },
+            __wbg_new_ee2291f50781bf1d: function() {
+                const ret = new Array();
+                return ret;
+            },
+            __wbg_new_from_slice_9a868026ffa4208a: function(arg0, arg1) {
+                const ret = new Uint8Array(getArrayU8FromWasm0(arg0, arg1));
+                return ret;
+            },
+            __wbg_new_with_str_and_init_4618dee4e950224f: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    const ret = new Request(getStringFromWasm0(arg0, arg1), arg2);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_new_with_str_sequence_sequence_636450f3f5527166: function() {
+                return handleError(function(arg0) {
+                    const ret = new Headers(arg0);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_nextElementSibling_8f66909f2ee45565: function(arg0) {
+                const ret = arg0.nextElementSibling;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_next_33784799010f1bbe: function(arg0) {
+                const ret = arg0.next;
+                return ret;
+            },
+            __wbg_next_f4aac29c42af995c: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.next();
+                    return ret;
+                }, arguments);
+            },
+            __wbg_now_aa4ccb83129e9e55: function() {
+                const ret = Date.now();
+                return ret;
+            },
+            __wbg_offsetTop_c36b688834dcdce3: function(arg0) {
+                const ret = arg0.offsetTop;
+                return ret;
+            },
+            __wbg_preventDefault_af59afb0f0a02e20: function(arg0) {
+                arg0.preventDefault();
+            },
+            __wbg_prototypesetcall_bc27214492979395: function(arg0, arg1, arg2) {
+                Uint8Array.prototype.set.call(getArrayU8FromWasm0(arg0, arg1), arg2);
+            },
+            __wbg_push_2baf45db356cf468: function(arg0, arg1) {
+                const ret = arg0.push(arg1);
+                return ret;
+            },
+            __wbg_querySelector_ecbc2550705b235f: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    const ret = arg0.querySelector(getStringFromWasm0(arg1, arg2));
+                    return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+                }, arguments);
+            },
+            __wbg_queueMicrotask_9833f9a49df95a49: function(arg0) {
+                const ret = arg0.queueMicrotask;
+                return ret;
+            },
+            __wbg_queueMicrotask_a72f977e97f23c5f: function(arg0) {
+                queueMicrotask(arg0);
+            },
+            __wbg_readAsArrayBuffer_b7dbf754ec0fe4bc: function() {
+                return handleError(function(arg0, arg1) {
+                    arg0.readAsArrayBuffer(arg1);
+                }, arguments);
+            },
+            __wbg_readyState_0df580078b419e28: function(arg0) {
+                const ret = arg0.readyState;
+                return ret;
+            },
+            __wbg_removeAttribute_2f2700a6f933a6be: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    arg0.removeAttribute(getStringFromWasm0(arg1, arg2));
+                }, arguments);
+            },
+            __wbg_removeChild_3745fc2545da50fa: function() {
+                return handleError(function(arg0, arg1) {
+                    const ret = arg0.removeChild(arg1);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_removeEventListener_261678bfcbd90907: function() {
+                return handleError(function(arg0, arg1, arg2, arg3, arg4) {
+                    arg0.removeEventListener(getStringFromWasm0(arg1, arg2), arg3, arg4 !== 0);
+                }, arguments);
+            },
+            __wbg_removeItem_f7b602722ed566dd: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    arg0.removeItem(getStringFromWasm0(arg1, arg2));
+                }, arguments);
+            },
+            __wbg_resolve_0076e10020304ede: function(arg0) {
+                const ret = Promise.resolve(arg0);
+                return ret;
+            },
+            __wbg_result_66ae42c57a28369f: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.result;
+                    return ret;
+                }, arguments);
+            },
+            __wbg_sessionStorage_83ad127fc90bc95e: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.sessionStorage;
+                    return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+                }, arguments);
+            },
+            __wbg_setAttribute_9e7d603908f63705: function() {
+                return handleError(function(arg0, arg1, arg2, arg3, arg4) {
+                    arg0.setAttribute(getStringFromWasm0(arg1, arg2), getStringFromWasm0(arg3, arg4));
+                }, arguments);
+            },
+            __wbg_setItem_c5fb0966b484ecd3: function() {
+                return handleError(function(arg0, arg1, arg2, arg3, arg4) {
+                    arg0.setItem(getStringFromWasm0(arg1, arg2), getStringFromWasm0(arg3, arg4));
+                }, arguments);
+            },
+            __wbg_setSelectionRange_844dcac6000ee674: function() {
+                return handleError(function(arg0, arg1, arg2) {
+                    arg0.setSelectionRange(arg1 >>> 0, arg2 >>> 0);
+                }, arguments);
+            },
+            __wbg_setTimeout_613a21b62dc655a1: function() {
+                return handleError(function(arg0, arg1) {
+                    const ret = setTimeout(arg0, arg1);
+                    return ret;
+                }, arguments);
+            },
+            __wbg_set_body_1fb0f1008bfc7df6: function(arg0, arg1) {
+                arg0.body = arg1;
+            },
+            __wbg_set_cache_7507c4cc5938ccc7: function(arg0, arg1) {
+                arg0.cache = __wbindgen_enum_RequestCache[arg1];
+            },
+            __wbg_set_capture_5beff5b8a7562085: function(arg0, arg1) {
+                arg0.capture = arg1 !== 0;
+            },
+            __wbg_set_checked_2ae6d148d8f3e080: function(arg0, arg1) {
+                arg0.checked = arg1 !== 0;
+            },
+            __wbg_set_credentials_e0dcce31ca532b7d: function(arg0, arg1) {
+                arg0.credentials = __wbindgen_enum_RequestCredentials[arg1];
+            },
+            __wbg_set_editable_02776dcc10bbfef9: function(arg0) {
+                set_editable(arg0 !== 0);
+            },
+            __wbg_set_headers_40ad33dcf016613f: function(arg0, arg1) {
+                arg0.headers = arg1;
+            },
+            __wbg_set_innerHTML_7af59a832a09a074: function(arg0, arg1, arg2) {
+                arg0.innerHTML = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_integrity_26f89e5d26fc7ef5: function(arg0, arg1, arg2) {
+                arg0.integrity = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_method_dcb32343247ec427: function(arg0, arg1, arg2) {
+                arg0.method = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_mode_74e772635acb96ec: function(arg0, arg1) {
+                arg0.mode = __wbindgen_enum_RequestMode[arg1];
+            },
+            __wbg_set_nodeValue_e265de7160527936: function(arg0, arg1, arg2) {
+                arg0.nodeValue = arg1 === 0 ? undefined : getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_once_8c45bbbfb71d1b34: function(arg0, arg1) {
+                arg0.once = arg1 !== 0;
+            },
+            __wbg_set_passive_31e9f895d2ac0046: function(arg0, arg1) {
+                arg0.passive = arg1 !== 0;
+            },
+            __wbg_set_redirect_cc128ab2411da334: function(arg0, arg1) {
+                arg0.redirect = __wbindgen_enum_RequestRedirect[arg1];
+            },
+            __wbg_set_referrer_efd0fb956d6b70b2: function(arg0, arg1, arg2) {
+                arg0.referrer = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_referrer_policy_465382f52c1a03d3: function(arg0, arg1) {
+                arg0.referrerPolicy = __wbindgen_enum_ReferrerPolicy[arg1];
+            },
+            __wbg_set_scrollTop_b7d4c4109ca5ccf9: function(arg0, arg1) {
+                arg0.scrollTop = arg1;
+            },
+            __wbg_set_signal_2dbd377604627f26: function(arg0, arg1) {
+                arg0.signal = arg1;
+            },
+            __wbg_set_title_f036cac582e3d5fe: function(arg0, arg1) {
+                let deferred0_0;
+                let deferred0_1;
+                try {
+                    deferred0_0 = arg0;
+                    deferred0_1 = arg1;
+                    set_title(getStringFromWasm0(arg0, arg1));
+                } finally{
+                    wasm.__wbindgen_free(deferred0_0, deferred0_1, 1);
+                }
+            },
+            __wbg_set_type_19b6e3a56c3fa1f1: function(arg0, arg1, arg2) {
+                arg0.type = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_type_ced89392955280fa: function(arg0, arg1, arg2) {
+                arg0.type = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_value_6689bac2533c9b37: function(arg0, arg1) {
+                let deferred0_0;
+                let deferred0_1;
+                try {
+                    deferred0_0 = arg0;
+                    deferred0_1 = arg1;
+                    set_value(getStringFromWasm0(arg0, arg1));
+                } finally{
+                    wasm.__wbindgen_free(deferred0_0, deferred0_1, 1);
+                }
+            },
+            __wbg_set_value_f5c1ffc19bac3037: function(arg0, arg1, arg2) {
+                arg0.value = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_set_value_fbd659f94bfb9181: function(arg0, arg1, arg2) {
+                arg0.value = getStringFromWasm0(arg1, arg2);
+            },
+            __wbg_signal_e5a49a2d9c65b7f6: function(arg0) {
+                const ret = arg0.signal;
+                return ret;
+            },
+            __wbg_static_accessor_GLOBAL_266715b9d96ba635: function() {
+                const ret = typeof global === 'undefined' ? null : global;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_static_accessor_GLOBAL_THIS_10fb7dc1ae063179: function() {
+                const ret = typeof globalThis === 'undefined' ? null : globalThis;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_static_accessor_SELF_0b583911f537483a: function() {
+                const ret = typeof self === 'undefined' ? null : self;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_static_accessor_WINDOW_d7f903d1508cbdc4: function() {
+                const ret = typeof window === 'undefined' ? null : window;
+                return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+            },
+            __wbg_status_ce0a98d3c57125f3: function(arg0) {
+                const ret = arg0.status;
+                return ret;
+            },
+            __wbg_textContent_acd42c63564a445e: function(arg0, arg1) {
+                const ret = arg1.textContent;
+                var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                var len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_text_ff3f476b3d6b1246: function() {
+                return handleError(function(arg0) {
+                    const ret = arg0.text();
+                    return ret;
+                }, arguments);
+            },
+            __wbg_then_c949d5a25a4e78f8: function(arg0, arg1, arg2) {
+                const ret = arg0.then(arg1, arg2);
+                return ret;
+            },
+            __wbg_then_e71170d78fcf8954: function(arg0, arg1) {
+                const ret = arg0.then(arg1);
+                return ret;
+            },
+            __wbg_toString_83fab3d7c865ecd3: function(arg0) {
+                const ret = arg0.toString();
+                return ret;
+            },
+            __wbg_type_85d2ff75f5f5adb0: function(arg0, arg1) {
+                const ret = arg1.type;
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_value_05305a761dfa3e0e: function(arg0, arg1) {
+                const ret = arg1.value;
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_value_50394658c248d9c1: function(arg0, arg1) {
+                const ret = arg1.value;
+                const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+                const len1 = WASM_VECTOR_LEN;
+                getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+                getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+            },
+            __wbg_value_f3c585ee8f5ba40c: function(arg0) {
+                const ret = arg0.value;
+                return ret;
+            },
+            __wbg_warn_88929063e36ba285: function(arg0, arg1, arg2, arg3) {
+                console.warn(arg0, arg1, arg2, arg3);
+            },
+            __wbindgen_generic_0000000000000001: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h8ddaea68dc6b2dd6);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000002: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000003: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures________invoke__ha711b1d23991acf4);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000004: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__hf305202a17678f8e);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000005: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea_5);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000006: function(arg0, arg1) {
+                const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__hae918579fb0233c5);
+                return ret;
+            },
+            __wbindgen_generic_0000000000000007: function(arg0, arg1) {
+                const ret = getStringFromWasm0(arg0, arg1);
+                return ret;
+            },
+            __wbindgen_init_externref_table: function() {
+                const table = wasm.__wbindgen_externrefs;
+                const offset = table.grow(4);
+                table.set(0, undefined);
+                table.set(offset + 0, undefined);
+                table.set(offset + 1, null);
+                table.set(offset + 2, true);
+                table.set(offset + 3, false);
+            }
+        };
+        return {
+            __proto__: null,
+            "./scrapnote_bg.js": import0,
+            "./snippets/scrapnote-3cf3b6b155192374/js/ace.js": mod,
+            "./snippets/scrapnote-3cf3b6b155192374/js/ace.js": mod,
+            "./snippets/scrapnote-3cf3b6b155192374/js/ace.js": mod,
+            "./snippets/scrapnote-3cf3b6b155192374/js/events.js": mod3,
+            "./snippets/scrapnote-3cf3b6b155192374/js/ace.js": mod,
+            "./snippets/scrapnote-3cf3b6b155192374/js/list.js": mod4,
+            "./snippets/scrapnote-3cf3b6b155192374/js/events.js": mod3,
+            "./snippets/scrapnote-3cf3b6b155192374/js/ipc.js": mod2,
+            "./snippets/scrapnote-3cf3b6b155192374/js/window.js": mod1
+        };
+    }
+    function wasm_bindgen__convert__closures_____invoke__hae918579fb0233c5(arg0, arg1) {
+        wasm.wasm_bindgen__convert__closures_____invoke__hae918579fb0233c5(arg0, arg1);
     }
-
-    let cachegetInt32Memory0 = null;
-    function getInt32Memory0() {
-        if (cachegetInt32Memory0 === null || cachegetInt32Memory0.buffer !== wasm.memory.buffer) {
-            cachegetInt32Memory0 = new Int32Array(wasm.memory.buffer);
+    function wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea(arg0, arg1, arg2) {
+        wasm.wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea(arg0, arg1, arg2);
+    }
+    function wasm_bindgen__convert__closures________invoke__ha711b1d23991acf4(arg0, arg1, arg2) {
+        wasm.wasm_bindgen__convert__closures________invoke__ha711b1d23991acf4(arg0, arg1, arg2);
+    }
+    function wasm_bindgen__convert__closures_____invoke__h8ddaea68dc6b2dd6(arg0, arg1, arg2) {
+        const ret = wasm.wasm_bindgen__convert__closures_____invoke__h8ddaea68dc6b2dd6(arg0, arg1, arg2);
+        if (ret[1]) {
+            throw takeFromExternrefTable0(ret[0]);
         }
-        return cachegetInt32Memory0;
     }
-
+    function wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea_5(arg0, arg1, arg2) {
+        wasm.wasm_bindgen__convert__closures_____invoke__h569872ee83dfa8ea_5(arg0, arg1, arg2);
+    }
+    function wasm_bindgen__convert__closures_____invoke__hf305202a17678f8e(arg0, arg1, arg2) {
+        const ptr0 = passStringToWasm0(arg2, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        wasm.wasm_bindgen__convert__closures_____invoke__hf305202a17678f8e(arg0, arg1, ptr0, len0);
+    }
+    const __wbindgen_enum_ReferrerPolicy = [
+        "",
+        "no-referrer",
+        "no-referrer-when-downgrade",
+        "origin",
+        "origin-when-cross-origin",
+        "unsafe-url",
+        "same-origin",
+        "strict-origin",
+        "strict-origin-when-cross-origin"
+    ];
+    const __wbindgen_enum_RequestCache = [
+        "default",
+        "no-store",
+        "reload",
+        "no-cache",
+        "force-cache",
+        "only-if-cached"
+    ];
+    const __wbindgen_enum_RequestCredentials = [
+        "omit",
+        "same-origin",
+        "include"
+    ];
+    const __wbindgen_enum_RequestMode = [
+        "same-origin",
+        "no-cors",
+        "cors",
+        "navigate"
+    ];
+    const __wbindgen_enum_RequestRedirect = [
+        "follow",
+        "error",
+        "manual"
+    ];
+    function addToExternrefTable0(obj) {
+        const idx = wasm.__externref_table_alloc();
+        wasm.__wbindgen_externrefs.set(idx, obj);
+        return idx;
+    }
+    const CLOSURE_DTORS = typeof FinalizationRegistry === 'undefined' ? {
+        register: ()=>{},
+        unregister: ()=>{}
+    } : new FinalizationRegistry((state)=>wasm.__wbindgen_destroy_closure(state.a, state.b));
     function debugString(val) {
-        // primitive types
         const type = typeof val;
         if (type == 'number' || type == 'boolean' || val == null) {
-            return  `${val}`;
+            return `${val}`;
         }
         if (type == 'string') {
             return `"${val}"`;
@@ -180,581 +962,302 @@
                 return 'Function';
             }
         }
-        // objects
         if (Array.isArray(val)) {
             const length = val.length;
             let debug = '[';
             if (length > 0) {
                 debug += debugString(val[0]);
             }
-            for(let i = 1; i < length; i++) {
+            for(let i = 1; i < length; i++){
                 debug += ', ' + debugString(val[i]);
             }
             debug += ']';
             return debug;
         }
-        // Test for built-in
         const builtInMatches = /\[object ([^\]]+)\]/.exec(toString.call(val));
         let className;
-        if (builtInMatches.length > 1) {
+        if (builtInMatches && builtInMatches.length > 1) {
             className = builtInMatches[1];
         } else {
-            // Failed to match the standard '[object ClassName]'
             return toString.call(val);
         }
         if (className == 'Object') {
-            // we're a user defined class or Object
-            // JSON.stringify avoids problems with cycles, and is generally much
-            // easier than looping through ownProperties of `val`.
             try {
                 return 'Object(' + JSON.stringify(val) + ')';
             } catch (_) {
                 return 'Object';
             }
         }
-        // errors
         if (val instanceof Error) {
             return `${val.name}: ${val.message}\n${val.stack}`;
         }
-        // TODO we could test for more things here, like `Set`s and `Map`s.
         return className;
     }
-
-    function makeMutClosure(arg0, arg1, dtor, f) {
-        const state = { a: arg0, b: arg1, cnt: 1, dtor };
-        const real = (...args) => {
-            // First up with a closure we increment the internal reference
-            // count. This ensures that the Rust closure environment won't
-            // be deallocated while we're invoking it.
+    function getArrayU8FromWasm0(ptr, len) {
+        ptr = ptr >>> 0;
+        return getUint8ArrayMemory0().subarray(ptr / 1, ptr / 1 + len);
+    }
+    let cachedDataViewMemory0 = null;
+    function getDataViewMemory0() {
+        if (cachedDataViewMemory0 === null || cachedDataViewMemory0.buffer.detached === true || cachedDataViewMemory0.buffer.detached === undefined && cachedDataViewMemory0.buffer !== wasm.memory.buffer) {
+            cachedDataViewMemory0 = new DataView(wasm.memory.buffer);
+        }
+        return cachedDataViewMemory0;
+    }
+    function getStringFromWasm0(ptr, len) {
+        return decodeText(ptr >>> 0, len);
+    }
+    let cachedUint8ArrayMemory0 = null;
+    function getUint8ArrayMemory0() {
+        if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
+            cachedUint8ArrayMemory0 = new Uint8Array(wasm.memory.buffer);
+        }
+        return cachedUint8ArrayMemory0;
+    }
+    function handleError(f, args) {
+        try {
+            return f.apply(this, args);
+        } catch (e) {
+            const idx = addToExternrefTable0(e);
+            wasm.__wbindgen_exn_store(idx);
+        }
+    }
+    function isLikeNone(x) {
+        return x === undefined || x === null;
+    }
+    function makeMutClosure(arg0, arg1, f) {
+        const state = {
+            a: arg0,
+            b: arg1,
+            cnt: 1
+        };
+        const real = (...args)=>{
             state.cnt++;
             const a = state.a;
             state.a = 0;
             try {
                 return f(a, state.b, ...args);
-            } finally {
-                if (--state.cnt === 0) {
-                    wasm.__wbindgen_export_2.get(state.dtor)(a, state.b);
-
-                } else {
-                    state.a = a;
-                }
+            } finally{
+                state.a = a;
+                real._wbg_cb_unref();
+            }
+        };
+        real._wbg_cb_unref = ()=>{
+            if (--state.cnt === 0) {
+                wasm.__wbindgen_destroy_closure(state.a, state.b);
+                state.a = 0;
+                CLOSURE_DTORS.unregister(state);
             }
         };
-        real.original = state;
-
+        CLOSURE_DTORS.register(real, state, state);
         return real;
     }
-
-    let stack_pointer = 32;
-
-    function addBorrowedObject(obj) {
-        if (stack_pointer == 1) throw new Error('out of js stack');
-        heap[--stack_pointer] = obj;
-        return stack_pointer;
-    }
-    function __wbg_adapter_22(arg0, arg1, arg2) {
-        try {
-            wasm._dyn_core__ops__function__FnMut___A____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__h87397acd854af82f(arg0, arg1, addBorrowedObject(arg2));
-        } finally {
-            heap[stack_pointer++] = undefined;
+    function passStringToWasm0(arg, malloc, realloc) {
+        if (realloc === undefined) {
+            const buf = cachedTextEncoder.encode(arg);
+            const ptr = malloc(buf.length, 1) >>> 0;
+            getUint8ArrayMemory0().subarray(ptr, ptr + buf.length).set(buf);
+            WASM_VECTOR_LEN = buf.length;
+            return ptr;
+        }
+        let len = arg.length;
+        let ptr = malloc(len, 1) >>> 0;
+        const mem = getUint8ArrayMemory0();
+        let offset = 0;
+        for(; offset < len; offset++){
+            const code = arg.charCodeAt(offset);
+            if (code > 0x7F) break;
+            mem[ptr + offset] = code;
         }
+        if (offset !== len) {
+            if (offset !== 0) {
+                arg = arg.slice(offset);
+            }
+            ptr = realloc(ptr, len, len = offset + arg.length * 3, 1) >>> 0;
+            const view = getUint8ArrayMemory0().subarray(ptr + offset, ptr + len);
+            const ret = cachedTextEncoder.encodeInto(arg, view);
+            offset += ret.written;
+            ptr = realloc(ptr, len, offset, 1) >>> 0;
+        }
+        WASM_VECTOR_LEN = offset;
+        return ptr;
     }
-
-    function __wbg_adapter_25(arg0, arg1, arg2) {
-        wasm._dyn_core__ops__function__FnMut__A____Output___R_as_wasm_bindgen__closure__WasmClosure___describe__invoke__h3c56854eb833dd9f(arg0, arg1, addHeapObject(arg2));
+    function takeFromExternrefTable0(idx) {
+        const value = wasm.__wbindgen_externrefs.get(idx);
+        wasm.__externref_table_dealloc(idx);
+        return value;
     }
-
-    /**
-    */
-    function run_app() {
-        wasm.run_app();
+    let cachedTextDecoder = new TextDecoder('utf-8', {
+        ignoreBOM: true,
+        fatal: true
+    });
+    cachedTextDecoder.decode();
+    const MAX_SAFARI_DECODE_BYTES = 2146435072;
+    let numBytesDecoded = 0;
+    function decodeText(ptr, len) {
+        numBytesDecoded += len;
+        if (numBytesDecoded >= MAX_SAFARI_DECODE_BYTES) {
+            cachedTextDecoder = new TextDecoder('utf-8', {
+                ignoreBOM: true,
+                fatal: true
+            });
+            cachedTextDecoder.decode();
+            numBytesDecoded = len;
+        }
+        return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
     }
-
-    function handleError(f) {
-        return function () {
-            try {
-                return f.apply(this, arguments);
-
-            } catch (e) {
-                wasm.__wbindgen_exn_store(addHeapObject(e));
-            }
+    const cachedTextEncoder = new TextEncoder();
+    if (!('encodeInto' in cachedTextEncoder)) {
+        cachedTextEncoder.encodeInto = function(arg, view) {
+            const buf = cachedTextEncoder.encode(arg);
+            view.set(buf);
+            return {
+                read: arg.length,
+                written: buf.length
+            };
         };
     }
-
-    async function load(module, imports) {
+    let WASM_VECTOR_LEN = 0;
+    let wasmModule, wasmInstance, wasm;
+    function __wbg_finalize_init(instance, module) {
+        wasmInstance = instance;
+        wasm = instance.exports;
+        wasmModule = module;
+        cachedDataViewMemory0 = null;
+        cachedUint8ArrayMemory0 = null;
+        wasm.__wbindgen_start();
+        return wasm;
+    }
+    async function __wbg_load(module, imports) {
         if (typeof Response === 'function' && module instanceof Response) {
-
+            if (!module.ok) {
+                throw new Error(`failed to fetch Wasm: ${module.status} ${module.statusText} fetching '${module.url}'`);
+            }
             if (typeof WebAssembly.instantiateStreaming === 'function') {
                 try {
                     return await WebAssembly.instantiateStreaming(module, imports);
-
                 } catch (e) {
-                    if (module.headers.get('Content-Type') != 'application/wasm') {
-                        console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
-
+                    const validResponse = expectedResponseType(module.type);
+                    if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
+                        console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
                     } else {
                         throw e;
                     }
                 }
             }
-
             const bytes = await module.arrayBuffer();
             return await WebAssembly.instantiate(bytes, imports);
-
         } else {
-
             const instance = await WebAssembly.instantiate(module, imports);
-
             if (instance instanceof WebAssembly.Instance) {
-                return { instance, module };
-
+                return {
+                    instance,
+                    module
+                };
             } else {
                 return instance;
             }
         }
-    }
-
-    async function init(input) {
-        if (typeof input === 'undefined') {
-            input = (document.currentScript && document.currentScript.src || new URL('scrapnote.js', document.baseURI).href).replace(/\.js$/, '_bg.wasm');
+        function expectedResponseType(type) {
+            switch(type){
+                case 'basic':
+                case 'cors':
+                case 'default':
+                    return true;
+            }
+            return false;
         }
-        const imports = {};
-        imports.wbg = {};
-        imports.wbg.__wbindgen_object_drop_ref = function(arg0) {
-            takeObject(arg0);
-        };
-        imports.wbg.__wbindgen_object_clone_ref = function(arg0) {
-            var ret = getObject(arg0);
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbindgen_cb_drop = function(arg0) {
-            const obj = takeObject(arg0).original;
-            if (obj.cnt-- == 1) {
-                obj.a = 0;
-                return true;
+    }
+    function initSync(module) {
+        if (wasm !== undefined) return wasm;
+        if (module !== undefined) {
+            if (Object.getPrototypeOf(module) === Object.prototype) {
+                ({ module } = module);
+            } else {
+                console.warn('using deprecated parameters for `initSync()`; pass a single object instead');
             }
-            var ret = false;
-            return ret;
-        };
-        imports.wbg.__wbg_getvalue_a3e7dcc91698b12f = function(arg0) {
-            var ret = get_value();
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_setvalue_2221437eede971de = function(arg0, arg1) {
-            try {
-                set_value(getStringFromWasm0(arg0, arg1));
-            } finally {
-                wasm.__wbindgen_free(arg0, arg1);
+        }
+        const imports = __wbg_get_imports();
+        if (!(module instanceof WebAssembly.Module)) {
+            module = new WebAssembly.Module(module);
+        }
+        const instance = new WebAssembly.Instance(module, imports);
+        return __wbg_finalize_init(instance, module);
+    }
+    async function __wbg_init(module_or_path) {
+        if (wasm !== undefined) return wasm;
+        if (module_or_path !== undefined) {
+            if (Object.getPrototypeOf(module_or_path) === Object.prototype) {
+                ({ module_or_path } = module_or_path);
+            } else {
+                console.warn('using deprecated parameters for the initialization function; pass a single object instead');
             }
-        };
-        imports.wbg.__wbg_seteditable_e8f790d22075d2ac = function(arg0) {
-            set_editable(arg0 !== 0);
-        };
-        imports.wbg.__wbindgen_string_new = function(arg0, arg1) {
-            var ret = getStringFromWasm0(arg0, arg1);
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_Window_6f26ab8994cdec9b = function(arg0) {
-            var ret = getObject(arg0).Window;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbindgen_is_undefined = function(arg0) {
-            var ret = getObject(arg0) === undefined;
-            return ret;
-        };
-        imports.wbg.__wbg_WorkerGlobalScope_65696f271e05e492 = function(arg0) {
-            var ret = getObject(arg0).WorkerGlobalScope;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_instanceof_Window_adf3196bdc02b386 = function(arg0) {
-            var ret = getObject(arg0) instanceof Window;
-            return ret;
-        };
-        imports.wbg.__wbg_document_6cc8d0b87c0a99b9 = function(arg0) {
-            var ret = getObject(arg0).document;
-            return isLikeNone(ret) ? 0 : addHeapObject(ret);
-        };
-        imports.wbg.__wbg_location_9b924f46d7090431 = function(arg0) {
-            var ret = getObject(arg0).location;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_fetch_1893f81c58855a39 = function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).fetch(getObject(arg1), getObject(arg2));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_createElement_5bdf88a5af9f17c5 = handleError(function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).createElement(getStringFromWasm0(arg1, arg2));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_createElementNS_ea14cb45a87a0719 = handleError(function(arg0, arg1, arg2, arg3, arg4) {
-            var ret = getObject(arg0).createElementNS(arg1 === 0 ? undefined : getStringFromWasm0(arg1, arg2), getStringFromWasm0(arg3, arg4));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_createTextNode_b3c9e3cb02f83ab5 = function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).createTextNode(getStringFromWasm0(arg1, arg2));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_querySelector_69fd5cd784bcc892 = handleError(function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).querySelector(getStringFromWasm0(arg1, arg2));
-            return isLikeNone(ret) ? 0 : addHeapObject(ret);
-        });
-        imports.wbg.__wbg_newwithstrandinit_d1de1bfcd175e38a = handleError(function(arg0, arg1, arg2) {
-            var ret = new Request(getStringFromWasm0(arg0, arg1), getObject(arg2));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_status_eb6dbb31556c329f = function(arg0) {
-            var ret = getObject(arg0).status;
-            return ret;
-        };
-        imports.wbg.__wbg_headers_c736e1fe38752cff = function(arg0) {
-            var ret = getObject(arg0).headers;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_arrayBuffer_dc33ab7b8cdf0d63 = handleError(function(arg0) {
-            var ret = getObject(arg0).arrayBuffer();
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_text_966d07536ca6ccdc = handleError(function(arg0) {
-            var ret = getObject(arg0).text();
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_instanceof_HtmlTextAreaElement_6c876047bbe08f92 = function(arg0) {
-            var ret = getObject(arg0) instanceof HTMLTextAreaElement;
-            return ret;
-        };
-        imports.wbg.__wbg_value_91d41b8dbd0b2f0b = function(arg0, arg1) {
-            var ret = getObject(arg1).value;
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_setvalue_1012134a2989f3ee = function(arg0, arg1, arg2) {
-            getObject(arg0).value = getStringFromWasm0(arg1, arg2);
-        };
-        imports.wbg.__wbg_instanceof_HtmlButtonElement_645b6f9d0d172e00 = function(arg0) {
-            var ret = getObject(arg0) instanceof HTMLButtonElement;
-            return ret;
-        };
-        imports.wbg.__wbg_settype_98bff8074314478e = function(arg0, arg1, arg2) {
-            getObject(arg0).type = getStringFromWasm0(arg1, arg2);
-        };
-        imports.wbg.__wbg_instanceof_HtmlInputElement_aaef9fb14eceaa9b = function(arg0) {
-            var ret = getObject(arg0) instanceof HTMLInputElement;
-            return ret;
-        };
-        imports.wbg.__wbg_setchecked_1a89c058f5ac906a = function(arg0, arg1) {
-            getObject(arg0).checked = arg1 !== 0;
-        };
-        imports.wbg.__wbg_settype_4f9e0a597bc38efe = function(arg0, arg1, arg2) {
-            getObject(arg0).type = getStringFromWasm0(arg1, arg2);
-        };
-        imports.wbg.__wbg_value_bff6f7ef104e077a = function(arg0, arg1) {
-            var ret = getObject(arg1).value;
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_setvalue_839acf17e43a847f = function(arg0, arg1, arg2) {
-            getObject(arg0).value = getStringFromWasm0(arg1, arg2);
-        };
-        imports.wbg.__wbg_setSelectionRange_91dde8b6b74fc61c = handleError(function(arg0, arg1, arg2) {
-            getObject(arg0).setSelectionRange(arg1 >>> 0, arg2 >>> 0);
-        });
-        imports.wbg.__wbg_ctrlKey_f080ec163dcc2703 = function(arg0) {
-            var ret = getObject(arg0).ctrlKey;
-            return ret;
-        };
-        imports.wbg.__wbg_isComposing_c0f97b8c3f5992b5 = function(arg0) {
-            var ret = getObject(arg0).isComposing;
-            return ret;
-        };
-        imports.wbg.__wbg_key_590d4d2a765d1b58 = function(arg0, arg1) {
-            var ret = getObject(arg1).key;
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_signal_0017288388f0d141 = function(arg0) {
-            var ret = getObject(arg0).signal;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_new_a82a2c2e1595c56f = handleError(function() {
-            var ret = new AbortController();
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_abort_bedcd7330ee18c55 = function(arg0) {
-            getObject(arg0).abort();
-        };
-        imports.wbg.__wbg_preventDefault_93d06688748bfc14 = function(arg0) {
-            getObject(arg0).preventDefault();
-        };
-        imports.wbg.__wbg_addEventListener_f0baf69c9c7425c9 = handleError(function(arg0, arg1, arg2, arg3, arg4) {
-            getObject(arg0).addEventListener(getStringFromWasm0(arg1, arg2), getObject(arg3), getObject(arg4));
-        });
-        imports.wbg.__wbg_removeEventListener_9ffcd175e0916270 = handleError(function(arg0, arg1, arg2, arg3, arg4) {
-            getObject(arg0).removeEventListener(getStringFromWasm0(arg1, arg2), getObject(arg3), arg4 !== 0);
-        });
-        imports.wbg.__wbg_namespaceURI_a4d52538ca0c6bae = function(arg0, arg1) {
-            var ret = getObject(arg1).namespaceURI;
-            var ptr0 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_removeAttribute_a705c6de4bb158c4 = handleError(function(arg0, arg1, arg2) {
-            getObject(arg0).removeAttribute(getStringFromWasm0(arg1, arg2));
-        });
-        imports.wbg.__wbg_setAttribute_727bdb9763037624 = handleError(function(arg0, arg1, arg2, arg3, arg4) {
-            getObject(arg0).setAttribute(getStringFromWasm0(arg1, arg2), getStringFromWasm0(arg3, arg4));
-        });
-        imports.wbg.__wbg_debug_d101e002eb92f20b = function(arg0, arg1, arg2, arg3) {
-            console.debug(getObject(arg0), getObject(arg1), getObject(arg2), getObject(arg3));
-        };
-        imports.wbg.__wbg_error_7f083efc6bc6752c = function(arg0) {
-            console.error(getObject(arg0));
-        };
-        imports.wbg.__wbg_error_cb872335132b1ef7 = function(arg0, arg1, arg2, arg3) {
-            console.error(getObject(arg0), getObject(arg1), getObject(arg2), getObject(arg3));
-        };
-        imports.wbg.__wbg_info_a25afde0ff8cd04a = function(arg0, arg1, arg2, arg3) {
-            console.info(getObject(arg0), getObject(arg1), getObject(arg2), getObject(arg3));
-        };
-        imports.wbg.__wbg_log_64f566ae90a6c43c = function(arg0, arg1, arg2, arg3) {
-            console.log(getObject(arg0), getObject(arg1), getObject(arg2), getObject(arg3));
-        };
-        imports.wbg.__wbg_warn_f632d7d3f55682b6 = function(arg0, arg1, arg2, arg3) {
-            console.warn(getObject(arg0), getObject(arg1), getObject(arg2), getObject(arg3));
-        };
-        imports.wbg.__wbg_focus_5f74cb245be46131 = handleError(function(arg0) {
-            getObject(arg0).focus();
-        });
-        imports.wbg.__wbg_fetch_8121e255050852c0 = function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).fetch(getObject(arg1), getObject(arg2));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_port_d1d4f617c8be38f0 = handleError(function(arg0, arg1) {
-            var ret = getObject(arg1).port;
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        });
-        imports.wbg.__wbg_lastChild_4a41600911ce94c7 = function(arg0) {
-            var ret = getObject(arg0).lastChild;
-            return isLikeNone(ret) ? 0 : addHeapObject(ret);
-        };
-        imports.wbg.__wbg_setnodeValue_ec452cd3716fee72 = function(arg0, arg1, arg2) {
-            getObject(arg0).nodeValue = arg1 === 0 ? undefined : getStringFromWasm0(arg1, arg2);
-        };
-        imports.wbg.__wbg_textContent_26582d13b9c5ea07 = function(arg0, arg1) {
-            var ret = getObject(arg1).textContent;
-            var ptr0 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbg_appendChild_77215fd672b162c5 = handleError(function(arg0, arg1) {
-            var ret = getObject(arg0).appendChild(getObject(arg1));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_insertBefore_ea385f1d7ec76e50 = handleError(function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).insertBefore(getObject(arg1), getObject(arg2));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_removeChild_f4829fcd2e376e1b = handleError(function(arg0, arg1) {
-            var ret = getObject(arg0).removeChild(getObject(arg1));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_newwithstrsequencesequence_08147e83d5fd2c0a = handleError(function(arg0) {
-            var ret = new Headers(getObject(arg0));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_get_27693110cb44e852 = function(arg0, arg1) {
-            var ret = getObject(arg0)[arg1 >>> 0];
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbindgen_is_function = function(arg0) {
-            var ret = typeof(getObject(arg0)) === 'function';
-            return ret;
-        };
-        imports.wbg.__wbindgen_is_object = function(arg0) {
-            const val = getObject(arg0);
-            var ret = typeof(val) === 'object' && val !== null;
-            return ret;
-        };
-        imports.wbg.__wbg_next_edda7e0003e5daf9 = function(arg0) {
-            var ret = getObject(arg0).next;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_next_2966fa909601a075 = handleError(function(arg0) {
-            var ret = getObject(arg0).next();
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_done_037d0a173aef1834 = function(arg0) {
-            var ret = getObject(arg0).done;
-            return ret;
-        };
-        imports.wbg.__wbg_value_e60bbfb7d52af62f = function(arg0) {
-            var ret = getObject(arg0).value;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_iterator_09191f8878ea9877 = function() {
-            var ret = Symbol.iterator;
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_get_0e3f2950cdf758ae = handleError(function(arg0, arg1) {
-            var ret = Reflect.get(getObject(arg0), getObject(arg1));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_call_8e95613cc6524977 = handleError(function(arg0, arg1) {
-            var ret = getObject(arg0).call(getObject(arg1));
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_new_e13110f81ae347cf = function() {
-            var ret = new Array();
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_from_2a5d647e62275bfd = function(arg0) {
-            var ret = Array.from(getObject(arg0));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_push_b46eeec52d2b03bb = function(arg0, arg1) {
-            var ret = getObject(arg0).push(getObject(arg1));
-            return ret;
-        };
-        imports.wbg.__wbg_toString_ae4f72e88ee70451 = function(arg0) {
-            var ret = getObject(arg0).toString();
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_newnoargs_f3b8a801d5d4b079 = function(arg0, arg1) {
-            var ret = new Function(getStringFromWasm0(arg0, arg1));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_is_333329c4a02916de = function(arg0, arg1) {
-            var ret = Object.is(getObject(arg0), getObject(arg1));
-            return ret;
-        };
-        imports.wbg.__wbg_new_3e06d4f36713e4cb = function() {
-            var ret = new Object();
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_resolve_2529512c3bb73938 = function(arg0) {
-            var ret = Promise.resolve(getObject(arg0));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_then_4a7a614abbbe6d81 = function(arg0, arg1) {
-            var ret = getObject(arg0).then(getObject(arg1));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_then_3b7ac098cfda2fa5 = function(arg0, arg1, arg2) {
-            var ret = getObject(arg0).then(getObject(arg1), getObject(arg2));
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbg_self_07b2f89e82ceb76d = handleError(function() {
-            var ret = self.self;
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_window_ba85d88572adc0dc = handleError(function() {
-            var ret = window.window;
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_globalThis_b9277fc37e201fe5 = handleError(function() {
-            var ret = globalThis.globalThis;
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_global_e16303fe83e1d57f = handleError(function() {
-            var ret = global.global;
-            return addHeapObject(ret);
-        });
-        imports.wbg.__wbg_set_304f2ec1a3ab3b79 = handleError(function(arg0, arg1, arg2) {
-            var ret = Reflect.set(getObject(arg0), getObject(arg1), getObject(arg2));
-            return ret;
-        });
-        imports.wbg.__wbindgen_string_get = function(arg0, arg1) {
-            const obj = getObject(arg1);
-            var ret = typeof(obj) === 'string' ? obj : undefined;
-            var ptr0 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbindgen_debug_string = function(arg0, arg1) {
-            var ret = debugString(getObject(arg1));
-            var ptr0 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
-            var len0 = WASM_VECTOR_LEN;
-            getInt32Memory0()[arg0 / 4 + 1] = len0;
-            getInt32Memory0()[arg0 / 4 + 0] = ptr0;
-        };
-        imports.wbg.__wbindgen_throw = function(arg0, arg1) {
-            throw new Error(getStringFromWasm0(arg0, arg1));
-        };
-        imports.wbg.__wbindgen_closure_wrapper541 = function(arg0, arg1, arg2) {
-            var ret = makeMutClosure(arg0, arg1, 230, __wbg_adapter_22);
-            return addHeapObject(ret);
-        };
-        imports.wbg.__wbindgen_closure_wrapper626 = function(arg0, arg1, arg2) {
-            var ret = makeMutClosure(arg0, arg1, 272, __wbg_adapter_25);
-            return addHeapObject(ret);
-        };
-        imports['./snippets/scrapnote-3cf3b6b155192374/js/ace.js'] = __wbg_star0;
-        imports['./snippets/scrapnote-3cf3b6b155192374/js/list.js'] = __wbg_star1;
-
-        if (typeof input === 'string' || (typeof Request === 'function' && input instanceof Request) || (typeof URL === 'function' && input instanceof URL)) {
-            input = fetch(input);
-        }
-
-        const { instance, module } = await load(await input, imports);
-
-        wasm = instance.exports;
-        init.__wbindgen_wasm_module = module;
-        wasm.__wbindgen_start();
-        return wasm;
+        }
+        if (module_or_path === undefined) {
+            module_or_path = new URL('scrapnote_bg.wasm', importMeta.url);
+        }
+        const imports = __wbg_get_imports();
+        if (typeof module_or_path === 'string' || typeof Request === 'function' && module_or_path instanceof Request || typeof URL === 'function' && module_or_path instanceof URL) {
+            module_or_path = fetch(module_or_path);
+        }
+        const { instance, module } = await __wbg_load(await module_or_path, imports);
+        return __wbg_finalize_init(instance, module);
     }
-
     function setUpMonacoEditor() {
-        require.config({ paths: { 'vs': 'https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min/vs' }});
-        window.MonacoEnvironment = { getWorkerUrl: () => proxy };
-        let proxy = URL.createObjectURL(new Blob([`
-        self.MonacoEnvironment = {
-            baseUrl: 'https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min'
+        window.MonacoEnvironment = {
+            getWorkerUrl: ()=>'./vendor/monaco/editor.worker.js'
         };
-        importScripts('https://cdnjs.cloudflare.com/ajax/libs/monaco-editor/0.21.2/min/vs/base/worker/workerMain.min.js');
-    `], { type: 'text/javascript' }));
-        require(["vs/editor/editor.main"], function () {
+        let url = new URL('./vendor/monaco/editor.js', document.baseURI).href;
+        import(url).then(function(monaco) {
             window.editor = monaco.editor.create(document.getElementById('editor'), {
                 automaticLayout: true,
                 language: 'markdown',
                 glyphMargin: false,
                 lineNumbers: 'off',
-                minimap: { enabled: false },
+                minimap: {
+                    enabled: false
+                },
                 readOnly: true,
-                fontFamily: 'Noto Sans JP',
+                fontFamily: '"Noto Sans JP", sans-serif',
                 folding: false,
                 lineDecorationsWidth: 0,
                 lineNumbersMinChars: 0,
                 scrollbar: {
                     vertical: "visible"
-                },
+                }
             });
             window.editor.addCommand(monaco.KeyCode.Escape, function() {
-                document.getElementById('editor')
-                    .dispatchEvent(new KeyboardEvent('keypress', {key: 'Escape'}));
+                document.getElementById('editor').dispatchEvent(new KeyboardEvent('keypress', {
+                    key: 'Escape'
+                }));
             }, '!suggestWidgetVisible');
+            window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyCode.KeyS, function() {
+                document.getElementById('editor').dispatchEvent(new KeyboardEvent('keypress', {
+                    key: 'Save'
+                }));
+            });
+            window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyD, function() {
+                document.getElementById('editor').dispatchEvent(new KeyboardEvent('keypress', {
+                    key: 'Discard'
+                }));
+            });
+            window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyV, function() {
+                document.getElementById('editor').dispatchEvent(new KeyboardEvent('keypress', {
+                    key: 'TogglePreview'
+                }));
+            });
+            window.editor.addCommand(monaco.KeyMod.CtrlCmd | monaco.KeyMod.Shift | monaco.KeyCode.KeyE, function() {
+                document.getElementById('editor').dispatchEvent(new KeyboardEvent('keypress', {
+                    key: 'OpenExternal'
+                }));
+            });
         });
-
     }
-
     async function main() {
-        await init('/scrapnote_bg.wasm');
+        await __wbg_init('/scrapnote_bg.wasm');
         run_app();
         setUpMonacoEditor();
     }
     main();
-
-}());
+    return {};
+})();